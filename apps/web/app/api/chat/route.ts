@@ -4,10 +4,15 @@ import {
   getLastPendingMessage,
   getMostRecentModel,
   getOrCreateThread,
+  getThreadById,
   loadChat,
   markMessageAsErrored,
   upsertMessage,
 } from "@/lib/actions/thread";
+import {
+  assertCanAppendMessage,
+  assertCanCreateThread,
+} from "@/lib/actions/usage-limits";
 import { getLanguageModel } from "@/lib/ai";
 import type { ModelOptions } from "@/lib/ai/models";
 import { getSystemPrompt } from "@/lib/ai/prompt";
@@ -83,6 +88,13 @@ export const POST = async (request: NextRequest) => {
 
     const streamId = generateId();
 
+    const [existingThread] = await getThreadById(threadId);
+    if (existingThread) {
+      await assertCanAppendMessage(threadId);
+    } else {
+      await assertCanCreateThread(userSession.user.id);
+    }
+
     await getOrCreateThread({
       id: threadId,
       userId: userSession.user.id,
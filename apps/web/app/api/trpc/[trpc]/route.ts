@@ -7,9 +7,10 @@ const handler = (req: Request) =>
     endpoint: "/api/trpc",
     req,
     router: appRouter,
-    createContext: () =>
+    createContext: ({ resHeaders }) =>
       createTRPCContext({
         headers: req.headers,
+        resHeaders,
       }),
   });
 
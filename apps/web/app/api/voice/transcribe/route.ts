@@ -0,0 +1,157 @@
+import { env } from "@/env";
+import { auth } from "@/lib/auth/server";
+import { OneChatSDKError } from "@/lib/errors";
+import { checkRateLimit, voiceRateLimit } from "@/lib/redis/rate-limits";
+import type { NextRequest } from "next/server";
+import { z } from "zod";
+
+const TRANSCRIPTION_CONFIG = {
+  model: "whisper-1",
+  maxFileSize: 25 * 1024 * 1024, // 25MB, same as OpenAI
+  multipartOverhead: 1024 * 1024, // Room for the other form fields and boundaries
+} as const;
+
+const transcriptionFormSchema = z.object({
+  file: z.instanceof(File),
+  language: z.string().min(2).max(5).optional(),
+  prompt: z.string().max(1000).optional(),
+  responseFormat: z
+    .enum(["json", "text", "srt", "verbose_json", "vtt"])
+    .default("json"),
+  apiKey: z.string().optional(),
+});
+
+/**
+ * Map a failed OpenAI response to an error the client can act on
+ * Only 401/403 on the user's own key are the user's fault, a rejected server key is ours
+ */
+const getUpstreamError = (status: number, usedUserKey: boolean) => {
+  if (status === 413) return new OneChatSDKError("file_too_large:voice");
+  if (status === 429) return new OneChatSDKError("rate_limit:voice");
+  if (status === 401 || status === 403) {
+    return new OneChatSDKError(
+      usedUserKey ? "forbidden:voice" : "internal_server_error:voice"
+    );
+  }
+  if (status >= 500) return new OneChatSDKError("bad_gateway:voice");
+
+  return new OneChatSDKError(
+    "bad_request:voice",
+    `Failed to transcribe audio: ${status}`
+  );
+};
+
+const withHeaders = (response: Response, headers: Headers): Response => {
+  headers.forEach((value, key) => {
+    response.headers.set(key, value);
+  });
+  return response;
+};
+
+/**
+ * Transcribe an audio file with OpenAI Whisper
+ * Uses the user's API key when provided, otherwise the server key under the voice rate limit
+ */
+export const POST = async (request: NextRequest) => {
+  const responseHeaders = new Headers();
+
+  try {
+    const userSession = await auth.api.getSession({ headers: request.headers });
+    if (!userSession) {
+      throw new OneChatSDKError("unauthorized:voice");
+    }
+
+    // Reject oversized uploads before buffering the body
+    const contentLength = Number(request.headers.get("content-length"));
+    if (
+      contentLength >
+      TRANSCRIPTION_CONFIG.maxFileSize + TRANSCRIPTION_CONFIG.multipartOverhead
+    ) {
+      throw new OneChatSDKError("file_too_large:voice");
+    }
+
+    const formData = await request.formData();
+    const parsed = transcriptionFormSchema.safeParse({
+      file: formData.get("file"),
+      language: formData.get("language") ?? undefined,
+      prompt: formData.get("prompt") ?? undefined,
+      responseFormat: formData.get("response_format") ?? undefined,
+      apiKey: formData.get("apiKey") ?? undefined,
+    });
+
+    if (!parsed.success) {
+      throw new OneChatSDKError(
+        "bad_request:voice",
+        parsed.error.issues
+          .map((issue) => `${issue.path.join(".")} ${issue.message}`)
+          .join(", ")
+      );
+    }
+
+    const { file, language, prompt, responseFormat, apiKey } = parsed.data;
+
+    if (file.size > TRANSCRIPTION_CONFIG.maxFileSize) {
+      throw new OneChatSDKError("file_too_large:voice");
+    }
+
+    if (!apiKey && !env.OPENAI_API_KEY) {
+      throw new OneChatSDKError("api_key_missing:voice");
+    }
+
+    // Rate limit free users (no API key provided)
+    if (!apiKey) {
+      const { success } = await checkRateLimit(
+        voiceRateLimit,
+        `voice_${userSession.user.id}`,
+        responseHeaders
+      );
+
+      if (!success) {
+        throw new OneChatSDKError("rate_limit:voice");
+      }
+    }
+
+    const upstreamForm = new FormData();
+    upstreamForm.append("file", file);
+    upstreamForm.append("model", TRANSCRIPTION_CONFIG.model);
+    upstreamForm.append("response_format", responseFormat);
+    if (language) upstreamForm.append("language", language);
+    if (prompt) upstreamForm.append("prompt", prompt);
+
+    const response = await fetch(
+      "https://api.openai.com/v1/audio/transcriptions",
+      {
+        method: "POST",
+        headers: {
+          Authorization: `Bearer ${apiKey || env.OPENAI_API_KEY}`,
+        },
+        body: upstreamForm,
+      }
+    );
+
+    if (!response.ok) {
+      const errorText = await response.text();
+      console.error("OpenAI transcription API error:", errorText);
+      throw getUpstreamError(response.status, Boolean(apiKey));
+    }
+
+    responseHeaders.set(
+      "Content-Type",
+      response.headers.get("Content-Type") ?? "application/json"
+    );
+
+    return new Response(response.body, {
+      status: 200,
+      headers: responseHeaders,
+    });
+  } catch (error) {
+    console.error("Error in POST /api/voice/transcribe:", error);
+
+    if (error instanceof OneChatSDKError) {
+      return withHeaders(error.toResponse(), responseHeaders);
+    }
+
+    const unknownError = new OneChatSDKError("internal_server_error:api");
+    return withHeaders(unknownError.toResponse(), responseHeaders);
+  }
+};
@@ -1,13 +1,24 @@
 import { Chat } from "@/components/chat";
-import { getPartialThreadData } from "@/lib/actions/partial-share";
+import {
+  getPartialShare,
+  getPartialThreadData,
+  isPartialShareExpired,
+} from "@/lib/actions/partial-share";
+import { getPartialShareMeta } from "@/lib/actions/share-meta";
+import { recordShareView } from "@/lib/actions/share-views";
 import type { Model } from "@/lib/ai";
+import { auth } from "@/lib/auth/server";
 import { siteConfig } from "@/lib/config";
 import { DEFAULT_CHAT_MODEL } from "@/lib/constants";
 import { resolveInitialModel } from "@/lib/utils";
 import type { MessageWithMetadata } from "@/types";
+import { Button } from "@workspace/ui/components/button";
+import { Home } from "lucide-react";
 import type { Metadata } from "next";
-import { cookies } from "next/headers";
+import { cookies, headers } from "next/headers";
+import Link from "next/link";
 import { notFound } from "next/navigation";
+import { after } from "next/server";
 
 interface PartialSharePageProps {
   params: Promise<{
@@ -21,9 +32,13 @@ export async function generateMetadata({
   const { token } = await params;
 
   try {
-    const partialThreadData = await getPartialThreadData(token);
+    // Only the summary is needed for OG tags, not the full message payload
+    const partialShare = await getPartialShare(token);
+    const partialShareMeta = partialShare
+      ? await getPartialShareMeta(partialShare)
+      : null;
 
-    if (!partialThreadData?.thread) {
+    if (!partialShareMeta) {
       return {
         title: "Shared Chat Not Found | One Chat",
         description:
@@ -55,20 +70,11 @@ export async function generateMetadata({
       };
     }
 
-    // Get the first user message for description
-    const firstUserMessage = partialThreadData.messages.find(
-      (msg) => msg.role === "user"
-    )?.content;
+    // Use the truncated first user message as the description
+    const description =
+      partialShareMeta.excerpt ?? "Shared AI conversation (partial)";
 
-    // Create a truncated description from the first message
-    const description = firstUserMessage
-      ? typeof firstUserMessage === "string"
-        ? firstUserMessage.slice(0, 160) +
-          (firstUserMessage.length > 160 ? "..." : "")
-        : "Shared AI conversation (partial)"
-      : "Shared AI conversation (partial)";
-
-    const title = partialThreadData.thread.title || "Shared Chat (Partial)";
+    const title = partialShareMeta.title || "Shared Chat (Partial)";
     const fullTitle = `${title} | One Chat`;
 
     return {
@@ -131,12 +137,56 @@ export async function generateMetadata({
   }
 }
 
+const PartialShareExpired = () => (
+  <div className="flex h-dvh w-full items-center justify-center bg-background">
+    <div className="flex max-w-2xl flex-col items-center justify-center px-4 text-center">
+      <div className="mb-12 space-y-4">
+        <h1 className="font-light text-2xl text-foreground tracking-tight">
+          This share link has expired
+        </h1>
+        <p className="mx-auto max-w-md text-lg text-muted-foreground leading-relaxed">
+          The owner of this conversation set the link to expire. Ask them for a
+          new one.
+        </p>
+      </div>
+      <Button asChild className="gap-2">
+        <Link href="/">
+          <Home className="size-4" />
+          Go Home
+        </Link>
+      </Button>
+    </div>
+  </div>
+);
+
 const PartialSharePage = async ({ params }: PartialSharePageProps) => {
   const { token } = await params;
 
   const [partialThreadData] = await Promise.all([getPartialThreadData(token)]);
 
-  if (!partialThreadData?.thread) return notFound();
+  if (!partialThreadData?.thread) {
+    // Pages cannot respond with 410 Gone, so expired links render their own notice instead of the 404
+    if (await isPartialShareExpired(token)) return <PartialShareExpired />;
+    return notFound();
+  }
+
+  // Count the view after the response is sent so it never slows the page down
+  // The owner opening their own link is not a view
+  const requestHeaders = await headers();
+  const session = await auth.api.getSession({ headers: requestHeaders });
+  if (session?.user?.id !== partialThreadData.thread.userId) {
+    const userAgent = requestHeaders.get("user-agent");
+    const ip =
+      requestHeaders.get("x-forwarded-for")?.split(",")[0]?.trim() ??
+      requestHeaders.get("x-real-ip");
+    after(async () => {
+      try {
+        await recordShareView(token, { userAgent, ip });
+      } catch (error) {
+        console.warn("Failed to record partial share view:", token, error);
+      }
+    });
+  }
 
   const cookieStore = await cookies();
   const chatModelFromCookie = cookieStore.get("chat-model")?.value as
@@ -11,7 +11,6 @@ import {
 } from "@workspace/ui/components/tooltip";
 import { cn } from "@workspace/ui/lib/utils";
 import { LinkIcon, PencilIcon, RotateCcwIcon, Split } from "lucide-react";
-import { nanoid } from "nanoid";
 import { memo, useState } from "react";
 import { ModelSelectionDropdown } from "./model-selection-dropdown";
 import { ProviderIcon } from "./model-selection-popover";
@@ -55,18 +54,17 @@ export const MessageActions = memo<MessageActionsProps>(
       trpc.thread.createPartialShare.useMutation();
 
     const handlePartialShare = () => {
-      const token = nanoid(12);
-      const shareUrl = `${window.location.origin}/share/partial/${token}`;
-
       const sharePromise = new Promise((resolve, reject) => {
         createPartialShareMutation.mutate(
           {
             threadId,
             messageId: message.id,
-            token,
+            // Repeated clicks copy the same link instead of minting a new share each time
+            reuseExisting: true,
           },
           {
-            onSuccess: () => {
+            onSuccess: ({ token }) => {
+              const shareUrl = `${window.location.origin}/share/partial/${token}`;
               navigator.clipboard.writeText(shareUrl);
               resolve({ shareUrl });
             },
@@ -1,6 +1,7 @@
 "use client";
 
 import { useApiKeys } from "@/hooks/use-api-keys";
+import { MAX_TTS_TEXT_LENGTH } from "@/lib/constants";
 import { trpc } from "@/lib/trpc/client";
 import { Button } from "@workspace/ui/components/button";
 import {
@@ -148,8 +149,10 @@ export const TTSButton = memo<TTSButtonProps>(
           return;
         }
 
-        if (text.length > 4000) {
-          toast.error(`Text is too long (${text.length}/4000 characters)`);
+        if (text.length > MAX_TTS_TEXT_LENGTH) {
+          toast.error(
+            `Text is too long (${text.length}/${MAX_TTS_TEXT_LENGTH} characters)`
+          );
           return;
         }
 
@@ -181,7 +184,7 @@ export const TTSButton = memo<TTSButtonProps>(
     const isLoading = generateSpeech.isPending;
     const hasOpenAIKey = Boolean(keys.openai);
     const hasGoogleKey = Boolean(keys.google);
-    const isTextTooLong = text.length > 4000;
+    const isTextTooLong = text.length > MAX_TTS_TEXT_LENGTH;
     const isDisabled = (isLoading && !isPlaying) || !text.trim();
 
     const handleButtonClick = () => {
@@ -232,7 +235,7 @@ export const TTSButton = memo<TTSButtonProps>(
               : isLoading
                 ? "Generating speech..."
                 : isTextTooLong
-                  ? `Speech - Text too long (${text.length}/4000 characters)`
+                  ? `Speech - Text too long (${text.length}/${MAX_TTS_TEXT_LENGTH} characters)`
                   : "Text to Speech"}
           </TooltipContent>
         </Tooltip>
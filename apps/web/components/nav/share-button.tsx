@@ -69,30 +69,29 @@ export const ShareButton = ({
   const [isPublic, setIsPublic] = useState(initialVisibility === "public");
   const [deletingTokens, setDeletingTokens] = useState<string[]>([]);
 
-  const { data: allPartialShares } = trpc.thread.getUserPartialShares.useQuery(
-    undefined,
-    { enabled: isOpen }
-  );
+  const { data: threadPartialShares } =
+    trpc.thread.getThreadPartialShares.useQuery(
+      { threadId },
+      { enabled: isOpen }
+    );
 
   useEffect(() => {
-    if (allPartialShares) {
-      const existingTokens = allPartialShares
-        .filter((share) => share.threadId === threadId)
-        .map((share) => share.token);
+    if (threadPartialShares) {
+      const existingTokens = threadPartialShares.map((share) => share.token);
 
       setDeletingTokens((prev) =>
         prev.filter((token) => existingTokens.includes(token))
       );
     }
-  }, [allPartialShares, threadId]);
+  }, [threadPartialShares]);
 
-  const partialShares = useMemo(() => {
-    const threadShares =
-      allPartialShares?.filter((share) => share.threadId === threadId) || [];
-    return threadShares.filter(
-      (share) => !deletingTokens.includes(share.token)
-    );
-  }, [allPartialShares, threadId, deletingTokens]);
+  const partialShares = useMemo(
+    () =>
+      threadPartialShares?.filter(
+        (share) => !deletingTokens.includes(share.token)
+      ) || [],
+    [threadPartialShares, deletingTokens]
+  );
 
   const deletePartialShare = trpc.thread.deletePartialShare.useMutation({
     onError: (error, variables) => {
@@ -249,6 +248,10 @@ export const ShareButton = ({
                       </div>
                       <div className="mt-1 text-muted-foreground text-xs">
                         Created {new Date(share.createdAt).toLocaleDateString()}
+                        {" · "}
+                        {share.expiresAt
+                          ? `Expires ${new Date(share.expiresAt).toLocaleDateString()}`
+                          : "Never expires"}
                       </div>
                     </div>
                     <div className="flex items-center gap-2">
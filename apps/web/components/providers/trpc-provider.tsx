@@ -2,7 +2,7 @@
 
 import { trpc } from "@/lib/trpc/client";
 import { QueryClient, QueryClientProvider } from "@tanstack/react-query";
-import { httpBatchLink } from "@trpc/client";
+import { httpBatchLink, httpLink, splitLink } from "@trpc/client";
 import { useState } from "react";
 
 export const TRPCProvider = ({ children }: React.PropsWithChildren) => {
@@ -10,8 +10,16 @@ export const TRPCProvider = ({ children }: React.PropsWithChildren) => {
   const [trpcClient] = useState(() =>
     trpc.createClient({
       links: [
-        httpBatchLink({
-          url: "/api/trpc",
+        // Voice procedures set per-call response headers (X-RateLimit-*, Retry-After, x-cache)
+        // A batch shares one set of headers, so these calls go unbatched
+        splitLink({
+          condition: (op) => op.path.startsWith("voice."),
+          true: httpLink({
+            url: "/api/trpc",
+          }),
+          false: httpBatchLink({
+            url: "/api/trpc",
+          }),
         }),
       ],
     })
@@ -5,6 +5,8 @@ export const env = createEnv({
   server: {
     DATABASE_URL: z.string().url(),
 
+    BETTER_AUTH_SECRET: z.string().min(1).optional(),
+
     UPSTASH_REDIS_REST_URL: z.string().url(),
     UPSTASH_REDIS_REST_TOKEN: z.string().min(1),
     UPSTASH_REDIS_URL: z.string().url(),
@@ -23,6 +25,10 @@ export const env = createEnv({
       .min(1)
       .startsWith("vercel_blob_rw_"),
 
+    // Caps against runaway scripts filling the database from one account
+    MAX_THREADS_PER_USER: z.coerce.number().int().positive().default(1000),
+    MAX_MESSAGES_PER_THREAD: z.coerce.number().int().positive().default(500),
+
     NODE_ENV: z.enum(["development", "production"]).default("development"),
   },
 
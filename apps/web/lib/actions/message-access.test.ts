@@ -0,0 +1,116 @@
+import { beforeEach, describe, expect, it, vi } from "vitest";
+import {
+  type MessageAccess,
+  MessageAccessError,
+  authorizeMessageAccess,
+} from "./message-access";
+
+type Row = {
+  message: { id: string; threadId: string };
+  thread: { id: string; userId: string; visibility: "private" | "public" };
+};
+
+const { rows } = vi.hoisted(() => ({ rows: { current: [] as unknown[] } }));
+
+vi.mock("@/lib/db", () => ({
+  db: {
+    select: () => ({
+      from: () => ({
+        innerJoin: () => ({
+          where: () => ({
+            limit: async () => rows.current,
+          }),
+        }),
+      }),
+    }),
+  },
+}));
+
+const rowFor = (visibility: "private" | "public"): Row => ({
+  message: { id: "message-1", threadId: "thread-1" },
+  thread: { id: "thread-1", userId: "owner", visibility },
+});
+
+const authorize = (userId: string | null, access: MessageAccess) =>
+  authorizeMessageAccess({ userId, messageId: "message-1", access });
+
+const expectAccessError = async (
+  result: Promise<unknown>,
+  reason: MessageAccessError["reason"]
+) => {
+  const error = await result.catch((e) => e);
+  expect(error).toBeInstanceOf(MessageAccessError);
+  expect(error).toMatchObject({ reason });
+};
+
+describe("authorizeMessageAccess", () => {
+  beforeEach(() => {
+    rows.current = [];
+  });
+
+  it("rejects a message that does not exist", async () => {
+    await expectAccessError(authorize("owner", "read"), "not_found");
+  });
+
+  describe("owner", () => {
+    it.each<MessageAccess>(["read", "write"])(
+      "can %s messages in a private thread",
+      async (access) => {
+        const row = rowFor("private");
+        rows.current = [row];
+
+        await expect(authorize("owner", access)).resolves.toBe(row);
+      }
+    );
+
+    it.each<MessageAccess>(["read", "write"])(
+      "can %s messages in a public thread",
+      async (access) => {
+        const row = rowFor("public");
+        rows.current = [row];
+
+        await expect(authorize("owner", access)).resolves.toBe(row);
+      }
+    );
+  });
+
+  describe("non-owner, private thread", () => {
+    it.each<MessageAccess>(["read", "write"])(
+      "cannot %s and is told the message does not exist",
+      async (access) => {
+        rows.current = [rowFor("private")];
+
+        await expectAccessError(authorize("someone-else", access), "not_found");
+      }
+    );
+
+    it("hides the message from signed-out users", async () => {
+      rows.current = [rowFor("private")];
+
+      await expectAccessError(authorize(null, "read"), "not_found");
+    });
+  });
+
+  describe("non-owner, public thread", () => {
+    it("can read", async () => {
+      const row = rowFor("public");
+      rows.current = [row];
+
+      await expect(authorize("someone-else", "read")).resolves.toBe(row);
+    });
+
+    it("cannot write", async () => {
+      rows.current = [rowFor("public")];
+
+      await expectAccessError(authorize("someone-else", "write"), "forbidden");
+    });
+
+    it("can read when signed out but never write", async () => {
+      const row = rowFor("public");
+      rows.current = [row];
+
+      await expect(authorize(null, "read")).resolves.toBe(row);
+      await expectAccessError(authorize(null, "write"), "forbidden");
+    });
+  });
+});
@@ -0,0 +1,58 @@
+import { db } from "@/lib/db";
+import { message as messageTable, thread } from "@/lib/db/schema/thread";
+import { eq } from "drizzle-orm";
+
+export type MessageAccess = "read" | "write";
+
+export class MessageAccessError extends Error {
+  reason: "not_found" | "forbidden";
+
+  constructor(reason: "not_found" | "forbidden") {
+    super(
+      reason === "not_found"
+        ? "Message not found"
+        : "You don't have access to this message"
+    );
+    this.name = "MessageAccessError";
+    this.reason = reason;
+  }
+}
+
+/**
+ * Load a message with its thread and verify the user may access it
+ * Owners can read and write, anyone can read messages in public threads
+ */
+export const authorizeMessageAccess = async ({
+  userId,
+  messageId,
+  access,
+}: {
+  userId: string | null;
+  messageId: string;
+  access: MessageAccess;
+}) => {
+  const [row] = await db
+    .select({ message: messageTable, thread })
+    .from(messageTable)
+    .innerJoin(thread, eq(messageTable.threadId, thread.id))
+    .where(eq(messageTable.id, messageId))
+    .limit(1);
+
+  if (!row) {
+    throw new MessageAccessError("not_found");
+  }
+
+  const isOwner = userId !== null && row.thread.userId === userId;
+  if (isOwner) return row;
+
+  // Hide private messages entirely rather than confirming they exist
+  if (row.thread.visibility !== "public") {
+    throw new MessageAccessError("not_found");
+  }
+
+  if (access === "write") {
+    throw new MessageAccessError("forbidden");
+  }
+
+  return row;
+};
@@ -0,0 +1,18 @@
+// Kept out of partial-share.ts because "use server" modules can only export async functions
+export class PartialShareConflictError extends Error {
+  constructor() {
+    super("A share with this token already exists");
+    this.name = "PartialShareConflictError";
+  }
+}
+
+// Bad input (invalid expiry, missing or backward anchor) or a share, thread or message the user can't see
+export class PartialShareError extends Error {
+  reason: "invalid" | "not_found";
+
+  constructor(reason: "invalid" | "not_found", message: string) {
+    super(message);
+    this.name = "PartialShareError";
+    this.reason = reason;
+  }
+}
@@ -0,0 +1,66 @@
+import { describe, expect, it } from "vitest";
+import { PartialShareError } from "./partial-share-errors";
+import { isExpired, resolveExpiresAt } from "./partial-share-expiry";
+
+const now = new Date("2026-01-01T12:00:00.000Z");
+
+describe("resolveExpiresAt", () => {
+  it("defaults to seven days", () => {
+    expect(resolveExpiresAt(undefined, now)).toEqual(
+      new Date("2026-01-08T12:00:00.000Z")
+    );
+  });
+
+  it("returns null for a share that never expires", () => {
+    expect(resolveExpiresAt(null, now)).toBeNull();
+  });
+
+  it.each([
+    ["30m", "2026-01-01T12:30:00.000Z"],
+    ["24h", "2026-01-02T12:00:00.000Z"],
+    ["7d", "2026-01-08T12:00:00.000Z"],
+    ["2w", "2026-01-15T12:00:00.000Z"],
+  ])("resolves the duration %s", (expiry, expected) => {
+    expect(resolveExpiresAt(expiry, now)).toEqual(new Date(expected));
+  });
+
+  it("accepts a future timestamp", () => {
+    expect(resolveExpiresAt("2026-01-01T12:00:01.000Z", now)).toEqual(
+      new Date("2026-01-01T12:00:01.000Z")
+    );
+  });
+
+  it.each([
+    ["a zero duration", "0d"],
+    ["a past timestamp", "2025-12-31T12:00:00.000Z"],
+    ["a timestamp exactly now", "2026-01-01T12:00:00.000Z"],
+    ["an unparseable value", "soon"],
+  ])("rejects %s as invalid input", (_, expiry) => {
+    expect(() => resolveExpiresAt(expiry, now)).toThrow(PartialShareError);
+    expect(() => resolveExpiresAt(expiry, now)).toThrow(
+      expect.objectContaining({ reason: "invalid" })
+    );
+  });
+});
+
+describe("isExpired", () => {
+  it("never expires a share without an expiry", () => {
+    expect(isExpired({ expiresAt: null }, now)).toBe(false);
+  });
+
+  it("is not expired a millisecond before expiry", () => {
+    expect(isExpired({ expiresAt: "2026-01-01T12:00:00.001Z" }, now)).toBe(
+      false
+    );
+  });
+
+  it("is expired exactly at expiry", () => {
+    expect(isExpired({ expiresAt: now.toISOString() }, now)).toBe(true);
+  });
+
+  it("is expired after expiry", () => {
+    expect(isExpired({ expiresAt: "2026-01-01T11:59:59.999Z" }, now)).toBe(
+      true
+    );
+  });
+});
@@ -0,0 +1,52 @@
+import type { PartialShare, PartialShareExpiry } from "./partial-share";
+import { PartialShareError } from "./partial-share-errors";
+
+export const PARTIAL_SHARE_TTL = 7 * 24 * 60 * 60; // 7 days in seconds
+
+const EXPIRY_DURATION_PATTERN = /^(\d+)([mhdw])$/;
+const EXPIRY_DURATION_UNITS = {
+  m: 60,
+  h: 60 * 60,
+  d: 24 * 60 * 60,
+  w: 7 * 24 * 60 * 60,
+} as const;
+
+/**
+ * Resolve an expiry input to an absolute date (null means never expires)
+ */
+export const resolveExpiresAt = (
+  expiry: PartialShareExpiry | undefined,
+  now: Date
+): Date | null => {
+  if (expiry === null) return null;
+  if (expiry === undefined) {
+    return new Date(now.getTime() + PARTIAL_SHARE_TTL * 1000);
+  }
+
+  const duration = EXPIRY_DURATION_PATTERN.exec(expiry);
+  const unit = duration?.[2] as keyof typeof EXPIRY_DURATION_UNITS | undefined;
+  const expiresAt =
+    duration && unit
+      ? new Date(
+          now.getTime() +
+            Number(duration[1]) * EXPIRY_DURATION_UNITS[unit] * 1000
+        )
+      : new Date(expiry);
+
+  if (Number.isNaN(expiresAt.getTime()) || expiresAt <= now) {
+    throw new PartialShareError(
+      "invalid",
+      "Expiry must be a future timestamp or a duration like 7d"
+    );
+  }
+
+  return expiresAt;
+};
+
+/**
+ * A share is expired from the exact instant of its expiresAt onwards
+ */
+export const isExpired = (
+  partialShare: Pick<PartialShare, "expiresAt">,
+  now = new Date()
+) => partialShare.expiresAt !== null && new Date(partialShare.expiresAt) <= now;
@@ -2,34 +2,191 @@
 
 import { auth } from "@/lib/auth/server";
 import { redis } from "@/lib/redis";
+import { partialShareTokenSchema } from "@/lib/schema";
+import { generateUUID } from "@/lib/utils";
 import { nanoid } from "nanoid";
 import { headers } from "next/headers";
-import { getMessageById, getThreadWithMessagesCached } from "./thread";
+import {
+  PartialShareConflictError,
+  PartialShareError,
+} from "./partial-share-errors";
+import { isExpired, resolveExpiresAt } from "./partial-share-expiry";
+import { invalidatePartialShareMeta } from "./share-meta";
+import {
+  type ShareViewStats,
+  deleteShareViewStats,
+  getShareViewStats,
+  recordShareFork,
+} from "./share-views";
+import {
+  branchOutFromMessage,
+  getMessageById,
+  getThreadWithMessagesCached,
+} from "./thread";
+
+/**
+ * snapshot: frozen at the anchor message
+ * live: follows the thread as it grows, still stopping at the anchor when one is set
+ */
+export type PartialShareMode = "snapshot" | "live";
 
 export interface PartialShare {
   token: string;
   threadId: string;
-  messageId: string;
+  messageId: string | null; // Anchor message, optional for live shares
+  mode: PartialShareMode;
   userId: string;
   createdAt: string;
-  expiresAt: string;
+  expiresAt: string | null; // null when the share never expires
 }
 
-const PARTIAL_SHARE_TTL = 7 * 24 * 60 * 60; // 7 days in seconds
+/**
+ * Share expiry: a duration such as "30m", "24h", "7d" or "2w", an ISO 8601 timestamp,
+ * or null for a share that never expires
+ */
+export type PartialShareExpiry = string | null;
+
+// Keep expired shares around for a while so viewers are told the link expired rather than not found
+const EXPIRED_PARTIAL_SHARE_RETENTION = 7 * 24 * 60 * 60; // 7 days in seconds
 const PARTIAL_SHARE_KEY_PREFIX = "partial_share:";
 const USER_PARTIAL_SHARES_KEY_PREFIX = "user_partial_shares:";
 
+/**
+ * Write a share to Redis, letting Redis purge it once the post-expiry retention has passed
+ * With onlyIfNew the write is skipped (returning false) when the token is already taken
+ */
+const storePartialShare = async (
+  partialShare: PartialShare,
+  { onlyIfNew = false }: { onlyIfNew?: boolean } = {}
+): Promise<boolean> => {
+  const key = `${PARTIAL_SHARE_KEY_PREFIX}${partialShare.token}`;
+  const value = JSON.stringify(partialShare);
+
+  // Re-storing may change the mode or anchor, so the cached preview summary is stale
+  if (!onlyIfNew) {
+    await invalidatePartialShareMeta(partialShare.token);
+  }
+
+  if (partialShare.expiresAt === null) {
+    const result = onlyIfNew
+      ? await redis.set(key, value, { nx: true })
+      : await redis.set(key, value);
+    return result === "OK";
+  }
+
+  const secondsUntilExpiry = Math.ceil(
+    (new Date(partialShare.expiresAt).getTime() - Date.now()) / 1000
+  );
+  const ttl =
+    Math.max(secondsUntilExpiry, 0) + EXPIRED_PARTIAL_SHARE_RETENTION;
+
+  const result = onlyIfNew
+    ? await redis.set(key, value, { ex: ttl, nx: true })
+    : await redis.set(key, value, { ex: ttl });
+  return result === "OK";
+};
+
+/**
+ * Read a share regardless of whether it has expired
+ */
+const readPartialShare = async (
+  token: string
+): Promise<PartialShare | null> => {
+  const data = await redis.get(`${PARTIAL_SHARE_KEY_PREFIX}${token}`);
+  if (!data) return null;
+
+  // Redis might return an object or string depending on the client
+  const partialShare =
+    typeof data === "string"
+      ? (JSON.parse(data) as PartialShare)
+      : (data as PartialShare);
+
+  // Shares created before live mode existed are snapshots
+  return { ...partialShare, mode: partialShare.mode ?? "snapshot" };
+};
+
+/**
+ * Verify the anchor message exists in the thread
+ */
+const getAnchorMessage = async (threadId: string, messageId: string) => {
+  const [message] = await getMessageById(messageId);
+  if (!message || message.threadId !== threadId) {
+    throw new PartialShareError(
+      "not_found",
+      "Message not found in this thread"
+    );
+  }
+
+  return message;
+};
+
+/**
+ * Check whether a new anchor message comes before the current anchor of a share
+ */
+const movesAnchorBackward = async (
+  partialShare: PartialShare,
+  nextAnchor: { createdAt: Date | string }
+) => {
+  if (!partialShare.messageId) return false;
+
+  const [currentAnchor] = await getMessageById(partialShare.messageId);
+  return (
+    currentAnchor !== undefined &&
+    new Date(nextAnchor.createdAt) < new Date(currentAnchor.createdAt)
+  );
+};
+
+/**
+ * List the active partial shares of a user, newest first
+ */
+const listUserPartialShares = async (
+  userId: string
+): Promise<PartialShare[]> => {
+  const userPartialSharesKey = `${USER_PARTIAL_SHARES_KEY_PREFIX}${userId}`;
+  const tokens = await redis.smembers(userPartialSharesKey);
+  const partialShares: PartialShare[] = [];
+  const purgedTokens: string[] = [];
+
+  for (const token of tokens) {
+    const partialShare = await readPartialShare(token);
+    if (!partialShare) {
+      purgedTokens.push(token);
+    } else if (!isExpired(partialShare)) {
+      partialShares.push(partialShare);
+    }
+  }
+
+  // Drop tokens whose share Redis has already purged
+  if (purgedTokens.length > 0) {
+    await redis.srem(userPartialSharesKey, ...purgedTokens);
+  }
+
+  // Sort by creation date (newest first)
+  return partialShares.sort(
+    (a, b) => new Date(b.createdAt).getTime() - new Date(a.createdAt).getTime()
+  );
+};
+
 /**
  * Create a partial share token for a thread up to a specific message
+ * Live shares may omit the message to share the whole thread as it grows
  */
 export const createPartialShare = async ({
   threadId,
   messageId,
   token: providedToken,
+  expiry,
+  mode = "snapshot",
+  reuseExisting = false,
+  bumpAnchor = false,
 }: {
   threadId: string;
-  messageId: string;
+  messageId?: string;
   token?: string;
+  expiry?: PartialShareExpiry;
+  mode?: PartialShareMode;
+  reuseExisting?: boolean;
+  bumpAnchor?: boolean;
 }): Promise<PartialShare> => {
   const session = await auth.api.getSession({
     headers: await headers(),
@@ -42,43 +199,166 @@ export const createPartialShare = async ({
   // Verify the thread exists and user owns it
   const thread = await getThreadWithMessagesCached(threadId);
   if (!thread?.thread || thread.thread.userId !== session.user.id) {
-    throw new Error("Thread not found or access denied");
+    throw new PartialShareError(
+      "not_found",
+      "Thread not found or access denied"
+    );
   }
 
-  // Verify the message exists in this thread
-  const [message] = await getMessageById(messageId);
-  if (!message || message.threadId !== threadId) {
-    throw new Error("Message not found in this thread");
+  const anchorMessage = messageId
+    ? await getAnchorMessage(threadId, messageId)
+    : undefined;
+
+  if (!anchorMessage && mode === "snapshot") {
+    throw new PartialShareError(
+      "invalid",
+      "Snapshot shares need a message to share up to"
+    );
   }
 
-  const token = providedToken || nanoid(12);
   const now = new Date();
-  const expiresAt = new Date(now.getTime() + PARTIAL_SHARE_TTL * 1000);
+  const expiresAt = resolveExpiresAt(expiry, now);
+
+  // Return the newest matching share instead of minting a new token
+  // A different anchor only reuses the share when bumpAnchor allows moving it forward, since that exposes more of the thread
+  if (reuseExisting) {
+    const existingShare = (await listUserPartialShares(session.user.id)).find(
+      (share) =>
+        share.threadId === threadId &&
+        share.mode === mode &&
+        (share.messageId === null) === !anchorMessage
+    );
+
+    const canReuse =
+      existingShare !== undefined &&
+      (!anchorMessage ||
+        anchorMessage.id === existingShare.messageId ||
+        (bumpAnchor &&
+          !(await movesAnchorBackward(existingShare, anchorMessage))));
+
+    if (existingShare && canReuse) {
+      // The reused share takes the requested expiry, so asking for "1h" never returns a share that never expires
+      const reusedShare: PartialShare = {
+        ...existingShare,
+        messageId: anchorMessage?.id ?? null,
+        expiresAt: expiresAt?.toISOString() ?? null,
+      };
+      await storePartialShare(reusedShare);
+      return reusedShare;
+    }
+  }
+
+  // Server actions can be called directly, so the router's input check is repeated here
+  const tokenCheck =
+    providedToken === undefined
+      ? undefined
+      : partialShareTokenSchema.safeParse(providedToken);
+  if (tokenCheck && !tokenCheck.success) {
+    throw new PartialShareError(
+      "invalid",
+      tokenCheck.error.issues[0]?.message ?? "Invalid share token"
+    );
+  }
+
+  const token = providedToken || nanoid(12);
 
   const partialShare: PartialShare = {
     token,
     threadId,
-    messageId,
+    messageId: messageId ?? null,
+    mode,
     userId: session.user.id,
     createdAt: now.toISOString(),
-    expiresAt: expiresAt.toISOString(),
+    expiresAt: expiresAt?.toISOString() ?? null,
   };
 
-  // Store the partial share in Redis
-  await redis.setex(
-    `${PARTIAL_SHARE_KEY_PREFIX}${token}`,
-    PARTIAL_SHARE_TTL,
-    JSON.stringify(partialShare)
-  );
+  // Store the partial share in Redis, never overwriting an existing share under the same token
+  const created = await storePartialShare(partialShare, { onlyIfNew: true });
+  if (!created) {
+    throw new PartialShareConflictError();
+  }
 
-  // Add to user's partial shares list
-  const userPartialSharesKey = `${USER_PARTIAL_SHARES_KEY_PREFIX}${session.user.id}`;
-  await redis.sadd(userPartialSharesKey, token);
-  await redis.expire(userPartialSharesKey, PARTIAL_SHARE_TTL);
+  // Add to user's partial shares list, stale tokens are pruned when the list is read
+  await redis.sadd(
+    `${USER_PARTIAL_SHARES_KEY_PREFIX}${session.user.id}`,
+    token
+  );
 
   return partialShare;
 };
 
+/**
+ * Update the expiry, mode or anchor of a partial share, omitted fields are left as they are
+ * The anchor can only move forward, and null removes it from a live share
+ * Also works on a recently expired share, which revives the link
+ */
+export const updatePartialShare = async ({
+  token,
+  expiry,
+  mode,
+  messageId,
+}: {
+  token: string;
+  expiry?: PartialShareExpiry;
+  mode?: PartialShareMode;
+  messageId?: string | null;
+}): Promise<PartialShare> => {
+  const session = await auth.api.getSession({
+    headers: await headers(),
+  });
+
+  if (!session?.user?.id) {
+    throw new Error("Unauthorized");
+  }
+
+  const partialShare = await readPartialShare(token);
+  if (!partialShare || partialShare.userId !== session.user.id) {
+    throw new PartialShareError(
+      "not_found",
+      "Partial share not found or access denied"
+    );
+  }
+
+  const nextMode = mode ?? partialShare.mode;
+  const nextMessageId =
+    messageId === undefined ? partialShare.messageId : messageId;
+
+  if (nextMode === "snapshot" && !nextMessageId) {
+    throw new PartialShareError(
+      "invalid",
+      "Snapshot shares need a message to share up to"
+    );
+  }
+
+  if (nextMessageId && nextMessageId !== partialShare.messageId) {
+    const nextAnchor = await getAnchorMessage(
+      partialShare.threadId,
+      nextMessageId
+    );
+
+    if (await movesAnchorBackward(partialShare, nextAnchor)) {
+      throw new PartialShareError(
+        "invalid",
+        "The shared message can only move forward"
+      );
+    }
+  }
+
+  const updatedShare: PartialShare = {
+    ...partialShare,
+    mode: nextMode,
+    messageId: nextMessageId,
+    expiresAt:
+      expiry === undefined
+        ? partialShare.expiresAt
+        : (resolveExpiresAt(expiry, new Date())?.toISOString() ?? null),
+  };
+
+  await storePartialShare(updatedShare);
+
+  return updatedShare;
+};
+
 /**
  * Get a partial share by token
  */
@@ -86,21 +366,8 @@ export const getPartialShare = async (
   token: string
 ): Promise<PartialShare | null> => {
   try {
-    const data = await redis.get(`${PARTIAL_SHARE_KEY_PREFIX}${token}`);
-    if (!data) return null;
-
-    // Redis might return an object or string depending on the client
-    const partialShare =
-      typeof data === "string"
-        ? (JSON.parse(data) as PartialShare)
-        : (data as PartialShare);
-
-    // Check if expired
-    if (new Date(partialShare.expiresAt) < new Date()) {
-      // Delete expired partial share without auth check
-      await redis.del(`${PARTIAL_SHARE_KEY_PREFIX}${token}`);
-      return null;
-    }
+    const partialShare = await readPartialShare(token);
+    if (!partialShare || isExpired(partialShare)) return null;
 
     return partialShare;
   } catch (error) {
@@ -109,6 +376,19 @@ export const getPartialShare = async (
   }
 };
 
+/**
+ * Check whether a token belongs to a share that has expired (as opposed to never existing)
+ */
+export const isPartialShareExpired = async (token: string) => {
+  try {
+    const partialShare = await readPartialShare(token);
+    return partialShare !== null && isExpired(partialShare);
+  } catch (error) {
+    console.error("Error checking partial share expiry:", error);
+    return false;
+  }
+};
+
 /**
  * Delete a partial share
  */
@@ -121,8 +401,8 @@ export const deletePartialShare = async (token: string): Promise<boolean> => {
     throw new Error("Unauthorized");
   }
 
-  // Get the partial share to verify ownership
-  const partialShare = await getPartialShare(token);
+  // Get the partial share to verify ownership, expired shares included
+  const partialShare = await readPartialShare(token);
   if (partialShare && partialShare.userId !== session.user.id) {
     throw new Error("Access denied");
   }
@@ -133,9 +413,42 @@ export const deletePartialShare = async (token: string): Promise<boolean> => {
   pipeline.srem(`${USER_PARTIAL_SHARES_KEY_PREFIX}${session.user.id}`, token);
 
   const results = await pipeline.exec();
+  await Promise.all([
+    deleteShareViewStats(token),
+    invalidatePartialShareMeta(token),
+  ]);
   return results !== null && Array.isArray(results[0]) && results[0][1] === 1;
 };
 
+/**
+ * Get view stats for a partial share owned by the current user
+ */
+export const getPartialShareStats = async ({
+  token,
+  days,
+}: {
+  token: string;
+  days: number;
+}): Promise<ShareViewStats> => {
+  const session = await auth.api.getSession({
+    headers: await headers(),
+  });
+
+  if (!session?.user?.id) {
+    throw new Error("Unauthorized");
+  }
+
+  const partialShare = await readPartialShare(token);
+  if (!partialShare || partialShare.userId !== session.user.id) {
+    throw new PartialShareError(
+      "not_found",
+      "Partial share not found or access denied"
+    );
+  }
+
+  return getShareViewStats(token, days);
+};
+
 /**
  * Get all partial shares for a user
  */
@@ -148,26 +461,30 @@ export const getUserPartialShares = async (): Promise<PartialShare[]> => {
     throw new Error("Unauthorized");
   }
 
-  const tokens = await redis.smembers(
-    `${USER_PARTIAL_SHARES_KEY_PREFIX}${session.user.id}`
-  );
-  const partialShares: PartialShare[] = [];
+  return listUserPartialShares(session.user.id);
+};
 
-  for (const token of tokens) {
-    const partialShare = await getPartialShare(token);
-    if (partialShare) {
-      partialShares.push(partialShare);
-    }
+/**
+ * Get the current user's partial shares for a single thread
+ */
+export const getThreadPartialShares = async (
+  threadId: string
+): Promise<PartialShare[]> => {
+  const session = await auth.api.getSession({
+    headers: await headers(),
+  });
+
+  if (!session?.user?.id) {
+    throw new Error("Unauthorized");
   }
 
-  // Sort by creation date (newest first)
-  return partialShares.sort(
-    (a, b) => new Date(b.createdAt).getTime() - new Date(a.createdAt).getTime()
-  );
+  const partialShares = await listUserPartialShares(session.user.id);
+  return partialShares.filter((share) => share.threadId === threadId);
 };
 
 /**
  * Get thread data for a partial share (up to the specified message)
+ * Live shares without an anchor return every message currently in the thread
  */
 export const getPartialThreadData = async (token: string) => {
   const partialShare = await getPartialShare(token);
@@ -180,27 +497,79 @@ export const getPartialThreadData = async (token: string) => {
     return null;
   }
 
-  // Get the target message to find the cutoff point
-  const [targetMessage] = await getMessageById(partialShare.messageId);
-  if (!targetMessage) {
-    return null;
+  let messages = thread.messages;
+
+  if (partialShare.messageId) {
+    // Get the target message to find the cutoff point
+    const [targetMessage] = await getMessageById(partialShare.messageId);
+    if (!targetMessage) {
+      return null;
+    }
+
+    // Filter messages up to and including the target message
+    messages = thread.messages.filter(
+      (msg) => new Date(msg.createdAt) <= new Date(targetMessage.createdAt)
+    );
   }
 
-  // Filter messages up to and including the target message
-  const messagesUpToTarget = thread.messages.filter(
-    (msg) => new Date(msg.createdAt) <= new Date(targetMessage.createdAt)
-  );
+  const titleSuffix = partialShare.mode === "live" ? "Live" : "Partial";
 
   return {
     thread: {
       ...thread.thread,
       id: partialShare.token, // Use token as ID for partial share
-      title: `${thread.thread.title} (Partial)`,
+      title: `${thread.thread.title} (${titleSuffix})`,
       visibility: "public" as const, // Partial shares are always public
     },
-    messages: messagesUpToTarget,
+    messages,
     isPartialShare: true,
+    mode: partialShare.mode,
     originalThreadId: partialShare.threadId,
     cutoffMessageId: partialShare.messageId,
   };
 };
+
+/**
+ * Copy the messages visible through a share into a new private thread owned by the viewer
+ * Access comes from the share token rather than thread ownership
+ */
+export const forkPartialShare = async ({
+  token,
+  newThreadId = generateUUID(),
+}: {
+  token: string;
+  newThreadId?: string;
+}): Promise<{ newThreadId: string; messageCount: number }> => {
+  const session = await auth.api.getSession({
+    headers: await headers(),
+  });
+
+  if (!session?.user?.id) {
+    throw new Error("Unauthorized");
+  }
+
+  const partialThreadData = await getPartialThreadData(token);
+  if (!partialThreadData) {
+    throw new PartialShareError(
+      "not_found",
+      "Partial share not found or expired"
+    );
+  }
+
+  // Branching from the last visible message copies exactly what the share shows
+  const lastVisibleMessage = partialThreadData.messages.at(-1);
+  if (!lastVisibleMessage) {
+    throw new PartialShareError("not_found", "No messages found to copy");
+  }
+
+  const result = await branchOutFromMessage({
+    messageId: lastVisibleMessage.id,
+    userId: session.user.id,
+    originalThreadId: partialThreadData.originalThreadId,
+    newThreadId,
+  });
+
+  await recordShareFork(token, result.newThreadId);
+
+  return result;
+};
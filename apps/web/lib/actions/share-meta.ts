@@ -0,0 +1,107 @@
+import { db } from "@/lib/db";
+import { message as messageTable, thread } from "@/lib/db/schema/thread";
+import { redis } from "@/lib/redis";
+import { and, asc, count, eq, lte } from "drizzle-orm";
+import type { PartialShare } from "./partial-share";
+
+export interface PartialShareMeta {
+  title: string;
+  messageCount: number;
+  excerpt: string | null;
+  createdAt: string;
+}
+
+const SHARE_META_KEY_PREFIX = "partial_share_meta:";
+const SHARE_META_TTL = 5 * 60; // 5 minutes in seconds
+const EXCERPT_LENGTH = 160;
+
+const getShareMetaKey = (token: string) => `${SHARE_META_KEY_PREFIX}${token}`;
+
+/**
+ * Get a lightweight summary of a partial share for link previews
+ * Counts and picks the first prompt in SQL instead of loading every message
+ * Takes an already resolved share so expired or deleted shares never serve a cached summary
+ */
+export const getPartialShareMeta = async (
+  partialShare: PartialShare
+): Promise<PartialShareMeta | null> => {
+  const cacheKey = getShareMetaKey(partialShare.token);
+
+  try {
+    const cached = await redis.get<PartialShareMeta>(cacheKey);
+    if (cached) return cached;
+  } catch (error) {
+    console.warn("Redis cache read failed for share meta:", cacheKey, error);
+  }
+
+  const [sharedThread] = await db
+    .select({ title: thread.title, createdAt: thread.createdAt })
+    .from(thread)
+    .where(eq(thread.id, partialShare.threadId))
+    .limit(1);
+
+  if (!sharedThread) return null;
+
+  let cutoff: Date | undefined;
+  if (partialShare.messageId) {
+    const [anchor] = await db
+      .select({ createdAt: messageTable.createdAt })
+      .from(messageTable)
+      .where(eq(messageTable.id, partialShare.messageId))
+      .limit(1);
+
+    if (!anchor) return null;
+    cutoff = anchor.createdAt;
+  }
+
+  const visibleMessages = and(
+    eq(messageTable.threadId, partialShare.threadId),
+    cutoff ? lte(messageTable.createdAt, cutoff) : undefined
+  );
+
+  const [[messageCount], [firstUserMessage]] = await Promise.all([
+    db.select({ value: count() }).from(messageTable).where(visibleMessages),
+    db
+      .select({ content: messageTable.content })
+      .from(messageTable)
+      .where(and(visibleMessages, eq(messageTable.role, "user")))
+      .orderBy(asc(messageTable.createdAt))
+      .limit(1),
+  ]);
+
+  const prompt = firstUserMessage?.content?.trim();
+  const titleSuffix = partialShare.mode === "live" ? "Live" : "Partial";
+
+  const meta: PartialShareMeta = {
+    title: `${sharedThread.title} (${titleSuffix})`,
+    messageCount: messageCount?.value ?? 0,
+    excerpt: prompt
+      ? prompt.slice(0, EXCERPT_LENGTH) +
+        (prompt.length > EXCERPT_LENGTH ? "..." : "")
+      : null,
+    createdAt: partialShare.createdAt,
+  };
+
+  try {
+    await redis.set(cacheKey, meta, { ex: SHARE_META_TTL });
+  } catch (error) {
+    console.warn("Redis cache write failed for share meta:", cacheKey, error);
+  }
+
+  return meta;
+};
+
+/**
+ * Drop the cached summary after a share's mode or anchor changes
+ */
+export const invalidatePartialShareMeta = async (token: string) => {
+  try {
+    await redis.del(getShareMetaKey(token));
+  } catch (error) {
+    console.warn(
+      "Redis cache invalidation failed for share meta:",
+      token,
+      error
+    );
+  }
+};
@@ -0,0 +1,156 @@
+import { createHmac } from "node:crypto";
+import { env } from "@/env";
+import { redis } from "@/lib/redis";
+
+export interface ShareAccessEntry {
+  viewedAt: string;
+  client: "bot" | "mobile" | "desktop" | "unknown";
+  ipHash: string | null;
+}
+
+export interface ShareViewStats {
+  viewCount: number;
+  botViewCount: number; // Link preview unfurls, kept out of viewCount and daily
+  lastViewedAt: string | null;
+  daily: { date: string; views: number }[];
+  recentAccess: ShareAccessEntry[];
+  forkCount: number;
+}
+
+const SHARE_VIEWS_KEY_PREFIX = "partial_share_views:";
+const SHARE_ACCESS_LOG_KEY_PREFIX = "partial_share_access:";
+const SHARE_FORKS_KEY_PREFIX = "partial_share_forks:";
+const DAY_FIELD_PREFIX = "day:";
+const MAX_ACCESS_LOG_ENTRIES = 100;
+// Stats are refreshed on every view and dropped after 90 days without one
+const SHARE_STATS_TTL = 90 * 24 * 60 * 60; // 90 days in seconds
+
+const getShareViewsKey = (token: string) =>
+  `${SHARE_VIEWS_KEY_PREFIX}${token}`;
+const getShareAccessLogKey = (token: string) =>
+  `${SHARE_ACCESS_LOG_KEY_PREFIX}${token}`;
+const getShareForksKey = (token: string) =>
+  `${SHARE_FORKS_KEY_PREFIX}${token}`;
+
+const toDay = (date: Date) => date.toISOString().slice(0, 10);
+
+// Coarse client class only, the raw user agent is never stored
+const getClientType = (
+  userAgent: string | null
+): ShareAccessEntry["client"] => {
+  if (!userAgent) return "unknown";
+  if (/bot|crawler|spider|preview|facebookexternalhit/i.test(userAgent)) {
+    return "bot";
+  }
+  if (/mobile|android|iphone|ipad/i.test(userAgent)) return "mobile";
+  return "desktop";
+};
+
+// Keyed with the server secret so the hash cannot be reversed by hashing every IP, nothing is stored without one
+const hashIp = (ip: string | null) =>
+  ip && env.BETTER_AUTH_SECRET
+    ? createHmac("sha256", env.BETTER_AUTH_SECRET)
+        .update(ip)
+        .digest("hex")
+        .slice(0, 16)
+    : null;
+
+/**
+ * Record a public view of a partial share
+ * Bots (link preview unfurls) only bump their own counter so they don't inflate views
+ * Meant to be called from after() so counting never delays the page
+ */
+export const recordShareView = async (
+  token: string,
+  { userAgent, ip }: { userAgent: string | null; ip: string | null }
+): Promise<void> => {
+  const now = new Date();
+  const day = toDay(now);
+  const viewsKey = getShareViewsKey(token);
+  const accessLogKey = getShareAccessLogKey(token);
+
+  const entry: ShareAccessEntry = {
+    viewedAt: now.toISOString(),
+    client: getClientType(userAgent),
+    ipHash: hashIp(ip),
+  };
+
+  const pipeline = redis.pipeline();
+  if (entry.client === "bot") {
+    pipeline.hincrby(viewsKey, "bots", 1);
+  } else {
+    pipeline.hincrby(viewsKey, "total", 1);
+    pipeline.hincrby(viewsKey, `${DAY_FIELD_PREFIX}${day}`, 1);
+    pipeline.hset(viewsKey, { lastViewedAt: entry.viewedAt });
+  }
+  pipeline.expire(viewsKey, SHARE_STATS_TTL);
+  pipeline.lpush(accessLogKey, JSON.stringify(entry));
+  pipeline.ltrim(accessLogKey, 0, MAX_ACCESS_LOG_ENTRIES - 1);
+  pipeline.expire(accessLogKey, SHARE_STATS_TTL);
+  await pipeline.exec();
+};
+
+/**
+ * Record the thread a partial share was forked into
+ */
+export const recordShareFork = async (
+  token: string,
+  threadId: string
+): Promise<void> => {
+  const forksKey = getShareForksKey(token);
+
+  const pipeline = redis.pipeline();
+  pipeline.sadd(forksKey, threadId);
+  pipeline.expire(forksKey, SHARE_STATS_TTL);
+  await pipeline.exec();
+};
+
+/**
+ * Get view counts for a partial share, bucketed by day (oldest first)
+ */
+export const getShareViewStats = async (
+  token: string,
+  days: number
+): Promise<ShareViewStats> => {
+  const [views, accessLog, forkCount] = await Promise.all([
+    redis.hgetall<Record<string, string | number>>(getShareViewsKey(token)),
+    redis.lrange(getShareAccessLogKey(token), 0, -1),
+    redis.scard(getShareForksKey(token)),
+  ]);
+
+  const today = new Date();
+  const daily = Array.from({ length: days }, (_, index) => {
+    const date = toDay(
+      new Date(today.getTime() - (days - 1 - index) * 24 * 60 * 60 * 1000)
+    );
+    return {
+      date,
+      views: Number(views?.[`${DAY_FIELD_PREFIX}${date}`] ?? 0),
+    };
+  });
+
+  return {
+    viewCount: Number(views?.total ?? 0),
+    botViewCount: Number(views?.bots ?? 0),
+    lastViewedAt: views?.lastViewedAt ? String(views.lastViewedAt) : null,
+    daily,
+    // Redis might return an object or string depending on the client
+    recentAccess: accessLog.map((item) =>
+      typeof item === "string"
+        ? (JSON.parse(item) as ShareAccessEntry)
+        : (item as ShareAccessEntry)
+    ),
+    forkCount,
+  };
+};
+
+/**
+ * Remove the stats of a deleted partial share
+ */
+export const deleteShareViewStats = async (token: string): Promise<void> => {
+  await redis.del(
+    getShareViewsKey(token),
+    getShareAccessLogKey(token),
+    getShareForksKey(token)
+  );
+};
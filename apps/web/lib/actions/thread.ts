@@ -13,12 +13,14 @@ import {
 } from "@/lib/cache/thread-list-cache";
 import { db } from "@/lib/db";
 import { message as messageTable, thread } from "@/lib/db/schema/thread";
+import { threadLockResource, withLock } from "@/lib/redis/lock";
 import type { UIMessage } from "ai";
 import { and, desc, eq, gt, gte, lt, lte, max, sql } from "drizzle-orm";
 import { headers } from "next/headers";
 import { cache } from "react";
 import type { Model } from "../ai";
 import { generateThreadTitle } from "../ai/action";
+import { assertCanCreateThread } from "./usage-limits";
 
 export const getThreadById = cache(async (id: string) => {
   return await db.select().from(thread).where(eq(thread.id, id)).limit(1);
@@ -333,21 +335,23 @@ export const deleteTrailingMessages = async ({
     return [];
   }
 
-  const removed = await db
-    .delete(messageTable)
-    .where(
-      and(
-        eq(messageTable.threadId, referenceMessage.threadId),
-        gt(messageTable.createdAt, referenceMessage.createdAt)
+  return withLock(threadLockResource(referenceMessage.threadId), async () => {
+    const removed = await db
+      .delete(messageTable)
+      .where(
+        and(
+          eq(messageTable.threadId, referenceMessage.threadId),
+          gt(messageTable.createdAt, referenceMessage.createdAt)
+        )
       )
-    )
-    .returning();
+      .returning();
 
-  if (removed.length > 0) {
-    invalidateThreadCache(referenceMessage.threadId);
-  }
+    if (removed.length > 0) {
+      invalidateThreadCache(referenceMessage.threadId);
+    }
 
-  return removed;
+    return removed;
+  });
 };
 
 export const deleteMessageAndTrailing = async ({
@@ -368,21 +372,23 @@ export const deleteMessageAndTrailing = async ({
     return [];
   }
 
-  const removed = await db
-    .delete(messageTable)
-    .where(
-      and(
-        eq(messageTable.threadId, referenceMessage.threadId),
-        gte(messageTable.createdAt, referenceMessage.createdAt)
+  return withLock(threadLockResource(referenceMessage.threadId), async () => {
+    const removed = await db
+      .delete(messageTable)
+      .where(
+        and(
+          eq(messageTable.threadId, referenceMessage.threadId),
+          gte(messageTable.createdAt, referenceMessage.createdAt)
+        )
       )
-    )
-    .returning();
+      .returning();
 
-  if (removed.length > 0) {
-    invalidateThreadCache(referenceMessage.threadId);
-  }
+    if (removed.length > 0) {
+      invalidateThreadCache(referenceMessage.threadId);
+    }
 
-  return removed;
+    return removed;
+  });
 };
 
 export const getMessageModel = async (
@@ -494,73 +500,77 @@ export const branchOutFromMessage = async ({
   originalThreadId: string;
   newThreadId: string;
 }): Promise<{ newThreadId: string; messageCount: number }> => {
-  const [originalThread] = await getThreadById(originalThreadId);
-  if (!originalThread) {
-    throw new Error("Original thread not found");
-  }
-
-  const [targetMessage] = await getMessageById(messageId);
-  if (!targetMessage) {
-    throw new Error("Target message not found");
-  }
+  await assertCanCreateThread(userId);
 
-  const messagesToCopy = await db
-    .select()
-    .from(messageTable)
-    .where(
-      and(
-        eq(messageTable.threadId, originalThreadId),
-        lte(messageTable.createdAt, targetMessage.createdAt)
-      )
-    )
-    .orderBy(messageTable.createdAt);
+  return withLock(threadLockResource(originalThreadId), async () => {
+    const [originalThread] = await getThreadById(originalThreadId);
+    if (!originalThread) {
+      throw new Error("Original thread not found");
+    }
 
-  if (messagesToCopy.length === 0) {
-    throw new Error("No messages found to copy");
-  }
+    const [targetMessage] = await getMessageById(messageId);
+    if (!targetMessage) {
+      throw new Error("Target message not found");
+    }
 
-  const [newThread] = await db
-    .insert(thread)
-    .values({
-      id: newThreadId,
-      userId,
-      title: originalThread.title,
-      originThreadId: originalThreadId,
-      visibility: "private",
-    })
-    .returning();
-
-  if (!newThread) {
-    throw new Error("Failed to create new thread");
-  }
+    const messagesToCopy = await db
+      .select()
+      .from(messageTable)
+      .where(
+        and(
+          eq(messageTable.threadId, originalThreadId),
+          lte(messageTable.createdAt, targetMessage.createdAt)
+        )
+      )
+      .orderBy(messageTable.createdAt);
+
+    if (messagesToCopy.length === 0) {
+      throw new Error("No messages found to copy");
+    }
+
+    const [newThread] = await db
+      .insert(thread)
+      .values({
+        id: newThreadId,
+        userId,
+        title: originalThread.title,
+        originThreadId: originalThreadId,
+        visibility: "private",
+      })
+      .returning();
 
-  const newMessageInserts = messagesToCopy.map((msg, index) => ({
-    id: `${newThreadId}-msg-${index}`,
-    threadId: newThreadId,
-    content: msg.content,
-    parts: msg.parts,
-    role: msg.role,
-    model: msg.model,
-    status: msg.status,
-    createdAt: new Date(Date.now() + index),
-    updatedAt: new Date(Date.now() + index),
-  }));
+    if (!newThread) {
+      throw new Error("Failed to create new thread");
+    }
+
+    const newMessageInserts = messagesToCopy.map((msg, index) => ({
+      id: `${newThreadId}-msg-${index}`,
+      threadId: newThreadId,
+      content: msg.content,
+      parts: msg.parts,
+      role: msg.role,
+      model: msg.model,
+      status: msg.status,
+      createdAt: new Date(Date.now() + index),
+      updatedAt: new Date(Date.now() + index),
+    }));
+
+    const insertedMessages = await db
+      .insert(messageTable)
+      .values(newMessageInserts)
+      .returning();
 
-  const insertedMessages = await db
-    .insert(messageTable)
-    .values(newMessageInserts)
-    .returning();
+    prePopulateBranchedThreadCache(newThreadId, insertedMessages, {
+      title: newThread.title,
+      userId: newThread.userId,
+      visibility: newThread.visibility,
+    });
 
-  prePopulateBranchedThreadCache(newThreadId, insertedMessages, {
-    title: newThread.title,
-    userId: newThread.userId,
-    visibility: newThread.visibility,
+    return {
+      newThreadId: newThread.id,
+      messageCount: insertedMessages.length,
+    };
   });
-
-  return {
-    newThreadId: newThread.id,
-    messageCount: insertedMessages.length,
-  };
 };
 
 export { branchOutFromMessage as branchOutFromMessageAlt };
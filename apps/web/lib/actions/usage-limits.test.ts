@@ -0,0 +1,77 @@
+import { OneChatSDKError } from "@/lib/errors";
+import { beforeEach, describe, expect, it, vi } from "vitest";
+import {
+  assertCanAppendMessage,
+  assertCanCreateThread,
+  getUsageLimits,
+} from "./usage-limits";
+
+const { countResult } = vi.hoisted(() => ({ countResult: { value: 0 } }));
+
+vi.mock("@/env", () => ({
+  env: { MAX_THREADS_PER_USER: 3, MAX_MESSAGES_PER_THREAD: 5 },
+}));
+vi.mock("@/lib/db", () => ({
+  db: {
+    select: () => ({
+      from: () => ({
+        where: async () => [{ value: countResult.value }],
+      }),
+    }),
+  },
+}));
+
+describe("assertCanCreateThread", () => {
+  beforeEach(() => {
+    countResult.value = 0;
+  });
+
+  it("allows a thread below the cap", async () => {
+    countResult.value = 2;
+    await expect(assertCanCreateThread("user-1")).resolves.toBeUndefined();
+  });
+
+  it("rejects a thread at the cap with the current usage", async () => {
+    countResult.value = 3;
+    const error = await assertCanCreateThread("user-1").catch((e) => e);
+
+    expect(error).toBeInstanceOf(OneChatSDKError);
+    expect(error).toMatchObject({
+      type: "limit_exceeded",
+      surface: "thread",
+      statusCode: 429,
+      cause: "3/3 threads used",
+    });
+  });
+});
+
+describe("assertCanAppendMessage", () => {
+  it("allows a message below the cap", async () => {
+    countResult.value = 4;
+    await expect(assertCanAppendMessage("thread-1")).resolves.toBeUndefined();
+  });
+
+  it("rejects a message at the cap with the current usage", async () => {
+    countResult.value = 5;
+    const error = await assertCanAppendMessage("thread-1").catch((e) => e);
+
+    expect(error).toBeInstanceOf(OneChatSDKError);
+    expect(error).toMatchObject({
+      type: "limit_exceeded",
+      surface: "chat",
+      statusCode: 429,
+      cause: "5/5 messages used",
+    });
+  });
+});
+
+describe("getUsageLimits", () => {
+  it("reports thread usage against both caps", async () => {
+    countResult.value = 2;
+
+    await expect(getUsageLimits("user-1")).resolves.toEqual({
+      threads: { used: 2, limit: 3 },
+      messagesPerThread: { limit: 5 },
+    });
+  });
+});
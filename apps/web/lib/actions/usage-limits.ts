@@ -0,0 +1,67 @@
+import { env } from "@/env";
+import { db } from "@/lib/db";
+import { message as messageTable, thread } from "@/lib/db/schema/thread";
+import { OneChatSDKError } from "@/lib/errors";
+import { count, eq } from "drizzle-orm";
+
+export interface UsageLimits {
+  threads: { used: number; limit: number };
+  messagesPerThread: { limit: number };
+}
+
+const countUserThreads = async (userId: string) => {
+  const [result] = await db
+    .select({ value: count() })
+    .from(thread)
+    .where(eq(thread.userId, userId));
+  return result?.value ?? 0;
+};
+
+const countThreadMessages = async (threadId: string) => {
+  const [result] = await db
+    .select({ value: count() })
+    .from(messageTable)
+    .where(eq(messageTable.threadId, threadId));
+  return result?.value ?? 0;
+};
+
+/**
+ * Reject a new thread once the user is at MAX_THREADS_PER_USER
+ * Every path that inserts a thread (chat, branch, fork) goes through this
+ */
+export const assertCanCreateThread = async (userId: string) => {
+  const used = await countUserThreads(userId);
+  if (used >= env.MAX_THREADS_PER_USER) {
+    throw new OneChatSDKError(
+      "limit_exceeded:thread",
+      `${used}/${env.MAX_THREADS_PER_USER} threads used`
+    );
+  }
+};
+
+/**
+ * Reject a new message once the thread is at MAX_MESSAGES_PER_THREAD
+ */
+export const assertCanAppendMessage = async (threadId: string) => {
+  const used = await countThreadMessages(threadId);
+  if (used >= env.MAX_MESSAGES_PER_THREAD) {
+    throw new OneChatSDKError(
+      "limit_exceeded:chat",
+      `${used}/${env.MAX_MESSAGES_PER_THREAD} messages used`
+    );
+  }
+};
+
+/**
+ * Get the user's thread usage against the caps
+ * The message cap applies per thread, so only its limit is reported
+ */
+export const getUsageLimits = async (
+  userId: string
+): Promise<UsageLimits> => ({
+  threads: {
+    used: await countUserThreads(userId),
+    limit: env.MAX_THREADS_PER_USER,
+  },
+  messagesPerThread: { limit: env.MAX_MESSAGES_PER_THREAD },
+});
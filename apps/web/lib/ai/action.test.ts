@@ -0,0 +1,80 @@
+import { describe, expect, it, vi } from "vitest";
+import { sanitizeTitle } from "./action";
+
+vi.mock("@/env", () => ({ env: {} }));
+vi.mock("./models", () => ({ getLanguageModel: vi.fn() }));
+
+const FAMILY_EMOJI = "\u{1F468}\u200D\u{1F469}\u200D\u{1F467}";
+
+const countGraphemes = (value: string) =>
+  Array.from(
+    new Intl.Segmenter(undefined, { granularity: "grapheme" }).segment(value)
+  ).length;
+
+describe("sanitizeTitle", () => {
+  it("keeps plain titles unchanged", () => {
+    expect(sanitizeTitle("Planning a trip to Lisbon")).toBe(
+      "Planning a trip to Lisbon"
+    );
+  });
+
+  it("keeps the zero-width joiner inside emoji sequences", () => {
+    expect(sanitizeTitle(`Family ${FAMILY_EMOJI} trip`)).toBe(
+      `Family ${FAMILY_EMOJI} trip`
+    );
+  });
+
+  it("strips LRM, RLM and ALM bidi marks", () => {
+    expect(sanitizeTitle("a\u200Eb\u200Fc\u061Cd")).toBe("a b c d");
+  });
+
+  it("strips bidi overrides and isolates", () => {
+    expect(sanitizeTitle("\u202Egnp.exe")).toBe("gnp.exe");
+    expect(sanitizeTitle("\u2066x\u2069")).toBe("x");
+  });
+
+  it("collapses control characters and whitespace", () => {
+    expect(sanitizeTitle("Plan\ttrip\n\nnow\u0000")).toBe("Plan trip now");
+  });
+
+  it("strips markdown", () => {
+    expect(sanitizeTitle('## **"Plan"** `trip`')).toBe("Plan trip");
+  });
+
+  it("returns an empty string for markdown-only titles", () => {
+    expect(sanitizeTitle("**``**")).toBe("");
+    expect(sanitizeTitle("### ")).toBe("");
+  });
+
+  it("keeps titles of exactly 80 graphemes", () => {
+    expect(sanitizeTitle("a".repeat(80))).toBe("a".repeat(80));
+    expect(sanitizeTitle(FAMILY_EMOJI.repeat(80))).toBe(
+      FAMILY_EMOJI.repeat(80)
+    );
+  });
+
+  it("truncates titles over 80 graphemes", () => {
+    expect(sanitizeTitle("a".repeat(81))).toBe(`${"a".repeat(77)}...`);
+  });
+
+  it("never splits an emoji when truncating", () => {
+    const title = sanitizeTitle(FAMILY_EMOJI.repeat(81));
+
+    expect(title).toBe(`${FAMILY_EMOJI.repeat(77)}...`);
+    expect(countGraphemes(title)).toBe(80);
+  });
+
+  it("never splits a combining sequence when truncating", () => {
+    const accented = "e\u0301";
+
+    expect(sanitizeTitle(accented.repeat(81))).toBe(
+      `${accented.repeat(77)}...`
+    );
+  });
+
+  it("drops trailing whitespace before the ellipsis", () => {
+    expect(sanitizeTitle(`${"x".repeat(76)} ${"y".repeat(10)}`)).toBe(
+      `${"x".repeat(76)}...`
+    );
+  });
+});
@@ -2,6 +2,46 @@ import { env } from "@/env";
 import { generateText } from "ai";
 import { getLanguageModel } from "./models";
 
+const DEFAULT_THREAD_TITLE = "New Thread";
+const MAX_TITLE_LENGTH = 80;
+const MAX_TITLE_PROMPT_INPUT_LENGTH = 400;
+
+// C0/C1 control characters (includes newlines and tabs), bidi marks (LRM, RLM, ALM) and embedding/override/isolate marks
+// Not all of \p{Cf}, which would also strip the zero-width joiner inside emoji sequences
+const CONTROL_AND_BIDI_CHARS =
+  /[\p{Cc}\u200E\u200F\u061C\u202A-\u202E\u2066-\u2069]/gu;
+const MARKDOWN_CHARS = /[`*"]|^#+\s*/g;
+
+const graphemeSegmenter = new Intl.Segmenter(undefined, {
+  granularity: "grapheme",
+});
+
+/**
+ * Cleans a raw title so it is safe to store and render verbatim.
+ * Strips control/bidi characters and markdown, collapses whitespace and caps the length.
+ * Truncates on grapheme boundaries so emoji and combined characters are never split.
+ */
+export const sanitizeTitle = (raw: string): string => {
+  const cleaned = raw
+    .replace(CONTROL_AND_BIDI_CHARS, " ")
+    .replace(/\s+/g, " ")
+    .trim()
+    .replace(MARKDOWN_CHARS, "")
+    .trim();
+
+  const graphemes = Array.from(
+    graphemeSegmenter.segment(cleaned),
+    ({ segment }) => segment
+  );
+
+  if (graphemes.length <= MAX_TITLE_LENGTH) return cleaned;
+
+  return `${graphemes
+    .slice(0, MAX_TITLE_LENGTH - 3)
+    .join("")
+    .trimEnd()}...`;
+};
+
 export type GenerateThreadTitlePayload = {
   userQuery: string;
   apiKeys: {
@@ -20,15 +60,20 @@ export const generateThreadTitle = async ({
     },
   });
 
+  const truncatedQuery = userQuery.slice(0, MAX_TITLE_PROMPT_INPUT_LENGTH);
+
   const { text } = await generateText({
     model,
-    prompt: `Generate a concise title for the following user query (max 60 characters). 
+    prompt: `Generate a concise title for the following user query (max 60 characters).
     Return only the title text, without any preambles or markdown formatting.
-    User Query: "${userQuery}"`,
+    User Query: "${truncatedQuery}"`,
     temperature: 0.2,
     topP: 0.9,
     maxTokens: 25,
   });
 
-  return text.trim().replace(/"/g, "");
+  // Fall back to an echo of the query if the model returned nothing usable
+  return (
+    sanitizeTitle(text) || sanitizeTitle(truncatedQuery) || DEFAULT_THREAD_TITLE
+  );
 };
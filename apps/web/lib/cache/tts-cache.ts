@@ -0,0 +1,76 @@
+import { createHash } from "node:crypto";
+import { redis } from "@/lib/redis";
+import { after } from "next/server";
+
+export type TTSCacheEntry = {
+  audio: string;
+  format: string;
+  voice: string;
+  text_length: number;
+  chunks: number;
+};
+
+type TTSCacheKeyParams = {
+  provider: string;
+  model: string;
+  voice: string;
+  speed: number;
+  stability?: number;
+  similarity?: number;
+  text: string;
+};
+
+// Cache TTL in seconds (24 hours)
+const CACHE_TTL = 24 * 60 * 60;
+
+// Skip caching audio larger than this (base64 length, ~1MB)
+const MAX_CACHED_AUDIO_SIZE = 1024 * 1024;
+
+// Cache key pattern for synthesized speech, hashed so long text stays a short key
+export const getTTSCacheKey = ({
+  provider,
+  model,
+  voice,
+  speed,
+  stability,
+  similarity,
+  text,
+}: TTSCacheKeyParams) => {
+  const hash = createHash("sha256")
+    .update(
+      JSON.stringify([provider, model, voice, speed, stability, similarity])
+    )
+    .update(text)
+    .digest("hex");
+
+  return `tts:${hash}`;
+};
+
+// Get synthesized speech from cache
+export const getTTSFromCache = async (
+  cacheKey: string
+): Promise<TTSCacheEntry | null> => {
+  try {
+    const cached = await redis.get(cacheKey);
+    if (cached) {
+      return cached as TTSCacheEntry;
+    }
+  } catch (error) {
+    console.warn("Redis cache read failed for TTS:", cacheKey, error);
+  }
+
+  return null;
+};
+
+// Set synthesized speech in cache (non-blocking using after)
+export const setTTSCache = (cacheKey: string, data: TTSCacheEntry) => {
+  if (data.audio.length > MAX_CACHED_AUDIO_SIZE) return;
+
+  after(async () => {
+    try {
+      await redis.set(cacheKey, data, { ex: CACHE_TTL });
+    } catch (error) {
+      console.warn("Redis cache write failed for TTS:", cacheKey, error);
+    }
+  });
+};
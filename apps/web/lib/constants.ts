@@ -7,6 +7,9 @@ export const IMAGE_GENERATION_MODEL: Model = "openai:gpt-imagegen";
 export const FALLBACK_MODEL: Model = "openai:gpt-4.1-mini";
 export const MAX_STEPS = 10;
 
+// Text-to-speech input cap, longer text is chunked server-side so this only guards against runaway input
+export const MAX_TTS_TEXT_LENGTH = 20_000;
+
 // Inspired by https://openrouter.ai/docs/use-cases/reasoning-tokens#reasoning-effort-level
 export const EFFORT_PERCENTAGE_MAP: Record<Effort, number> = {
   low: 0.2, // 20% of max_tokens
@@ -10,7 +10,9 @@ export type ErrorType =
   | "file_too_large"
   | "unsupported_file_type"
   | "rate_limit"
-  | "upload_failed";
+  | "limit_exceeded"
+  | "upload_failed"
+  | "bad_gateway";
 
 export type Surface =
   | "auth"
@@ -21,7 +23,8 @@ export type Surface =
   | "files"
   | "models"
   | "thread"
-  | "attachment";
+  | "attachment"
+  | "voice";
 
 export type ErrorCode = `${ErrorType}:${Surface}`;
 
@@ -37,6 +40,7 @@ export const visibilityBySurface: Record<Surface, ErrorVisibility> = {
   models: "response",
   thread: "response",
   attachment: "response",
+  voice: "response",
 };
 export class OneChatSDKError extends Error {
   type: ErrorType;
@@ -112,6 +116,8 @@ export function getMessageByErrorCode(errorCode: ErrorCode): string {
       return "This chat belongs to another user. Please check the chat ID and try again.";
     case "rate_limit:chat":
       return "You have exceeded your maximum number of messages. Please try again later.";
+    case "limit_exceeded:chat":
+      return "This chat has reached its maximum number of messages. Start a new chat to continue.";
 
     // Model errors
     case "model_not_found:models":
@@ -136,6 +142,8 @@ export function getMessageByErrorCode(errorCode: ErrorCode): string {
       return "The requested thread was not found. Please check the thread ID and try again.";
     case "forbidden:thread":
       return "This thread belongs to another user. Please check the thread ID and try again.";
+    case "limit_exceeded:thread":
+      return "You have reached the maximum number of threads. Delete old threads to create new ones.";
 
     // Attachment errors
     case "not_found:attachment":
@@ -145,6 +153,22 @@ export function getMessageByErrorCode(errorCode: ErrorCode): string {
     case "unauthorized:attachment":
       return "You need to sign in to access this attachment. Please sign in and try again.";
 
+    // Voice errors
+    case "unauthorized:voice":
+      return "You need to sign in to use voice features. Please sign in and try again.";
+    case "bad_request:voice":
+      return "The audio request couldn't be processed. Please check your input and try again.";
+    case "file_too_large:voice":
+      return "Audio file is too large. Maximum file size is 25MB.";
+    case "rate_limit:voice":
+      return "Voice limit reached. Please try again later or add your API key.";
+    case "api_key_missing:voice":
+      return "OpenAI API key is missing for voice features.";
+    case "forbidden:voice":
+      return "Your OpenAI API key was rejected. Please check the key and try again.";
+    case "bad_gateway:voice":
+      return "The voice service is temporarily unavailable. Please try again shortly.";
+
     default:
       return "Something went wrong. Please try again later.";
   }
@@ -161,6 +185,7 @@ function getStatusCodeByType(type: ErrorType): number {
     case "not_found":
       return 404;
     case "rate_limit":
+    case "limit_exceeded":
       return 429;
     case "file_too_large":
       return 413;
@@ -174,6 +199,8 @@ function getStatusCodeByType(type: ErrorType): number {
       return 503;
     case "internal_server_error":
       return 500;
+    case "bad_gateway":
+      return 502;
     default:
       return 500;
   }
@@ -0,0 +1,27 @@
+import { vi } from "vitest";
+
+/**
+ * In-memory stand-in for the Upstash client, covering the commands the lock uses
+ * Test-only, swap it in with vi.mock("@/lib/redis")
+ */
+export const fakeRedisStore = new Map<string, string>();
+
+export const fakeRedis = {
+  set: vi.fn(
+    async (key: string, value: string, options?: { nx?: boolean }) => {
+      if (options?.nx && fakeRedisStore.has(key)) return null;
+      fakeRedisStore.set(key, value);
+      return "OK";
+    }
+  ),
+  // Only evaluates the lock release script: delete the key if it holds the token
+  eval: vi.fn(async (_script: string, [key]: string[], [token]: string[]) => {
+    if (key === undefined || fakeRedisStore.get(key) !== token) return 0;
+    fakeRedisStore.delete(key);
+    return 1;
+  }),
+  del: vi.fn(
+    async (...keys: string[]) =>
+      keys.filter((key) => fakeRedisStore.delete(key)).length
+  ),
+};
@@ -0,0 +1,96 @@
+import { afterEach, describe, expect, it, vi } from "vitest";
+import { fakeRedis, fakeRedisStore } from "./fake-redis";
+import { LockUnavailableError, withLock } from "./lock";
+
+vi.mock("@/lib/redis", async () => ({
+  redis: (await import("./fake-redis")).fakeRedis,
+}));
+
+const options = { timeoutMs: 50, retryDelayMs: 10 };
+
+describe("withLock", () => {
+  afterEach(() => {
+    fakeRedisStore.clear();
+    vi.clearAllMocks();
+  });
+
+  it("lets exactly one of two concurrent callers run", async () => {
+    let finishFirst!: () => void;
+    const firstRunning = new Promise<void>((resolve) => {
+      finishFirst = resolve;
+    });
+    const second = vi.fn(async () => "second");
+
+    const firstResult = withLock(
+      "thread:1",
+      async () => {
+        await firstRunning;
+        return "first";
+      },
+      options
+    );
+    const secondResult = withLock("thread:1", second, options);
+
+    await expect(secondResult).rejects.toBeInstanceOf(LockUnavailableError);
+    expect(second).not.toHaveBeenCalled();
+
+    finishFirst();
+    await expect(firstResult).resolves.toBe("first");
+  });
+
+  it("waits for a lock that frees up before the timeout", async () => {
+    const first = withLock(
+      "thread:1",
+      async () => {
+        await new Promise((resolve) => setTimeout(resolve, 20));
+        return "first";
+      },
+      options
+    );
+    const second = withLock("thread:1", async () => "second", options);
+
+    await expect(Promise.all([first, second])).resolves.toEqual([
+      "first",
+      "second",
+    ]);
+  });
+
+  it("does not block different resources", async () => {
+    await expect(
+      Promise.all([
+        withLock("thread:1", async () => 1, options),
+        withLock("thread:2", async () => 2, options),
+      ])
+    ).resolves.toEqual([1, 2]);
+  });
+
+  it("releases the lock when the operation throws", async () => {
+    await expect(
+      withLock(
+        "thread:1",
+        async () => {
+          throw new Error("boom");
+        },
+        options
+      )
+    ).rejects.toThrow("boom");
+
+    expect(fakeRedisStore.size).toBe(0);
+  });
+
+  it("does not fail the operation when releasing the lock fails", async () => {
+    const warn = vi.spyOn(console, "warn").mockImplementation(() => undefined);
+    fakeRedis.eval.mockRejectedValueOnce(new Error("network"));
+
+    await expect(
+      withLock("thread:1", async () => "done", options)
+    ).resolves.toBe("done");
+    expect(warn).toHaveBeenCalledWith(
+      "Failed to release lock:",
+      "lock:thread:1",
+      expect.any(Error)
+    );
+
+    warn.mockRestore();
+  });
+});
@@ -0,0 +1,110 @@
+import { nanoid } from "nanoid";
+import { redis } from ".";
+
+const LOCK_KEY_PREFIX = "lock:";
+const DEFAULT_LOCK_TTL_MS = 30_000;
+const DEFAULT_ACQUIRE_TIMEOUT_MS = 2000;
+const DEFAULT_RETRY_DELAY_MS = 100;
+
+// Only delete the key if it still holds our token, so an expired lock re-acquired by someone else is never released
+const RELEASE_SCRIPT = `
+if redis.call("get", KEYS[1]) == ARGV[1] then
+  return redis.call("del", KEYS[1])
+else
+  return 0
+end
+`;
+
+export interface DistributedLock {
+  key: string;
+  token: string;
+  release: () => Promise<void>;
+}
+
+export interface LockOptions {
+  ttlMs?: number;
+  timeoutMs?: number;
+  retryDelayMs?: number;
+}
+
+export class LockUnavailableError extends Error {
+  resource: string;
+
+  constructor(resource: string) {
+    super("Another operation is already in progress. Please try again.");
+    this.name = "LockUnavailableError";
+    this.resource = resource;
+  }
+}
+
+export const threadLockResource = (threadId: string) => `thread:${threadId}`;
+
+/**
+ * Try to acquire a lock once (SET NX PX), returns null if it is already held
+ */
+export const acquireLock = async (
+  resource: string,
+  ttlMs = DEFAULT_LOCK_TTL_MS
+): Promise<DistributedLock | null> => {
+  const key = `${LOCK_KEY_PREFIX}${resource}`;
+  const token = nanoid();
+
+  const result = await redis.set(key, token, { nx: true, px: ttlMs });
+  if (result !== "OK") return null;
+
+  return {
+    key,
+    token,
+    release: async () => {
+      await redis.eval(RELEASE_SCRIPT, [key], [token]);
+    },
+  };
+};
+
+/**
+ * Retry acquiring a lock until it succeeds or the timeout elapses
+ */
+export const acquireLockWithTimeout = async (
+  resource: string,
+  {
+    ttlMs = DEFAULT_LOCK_TTL_MS,
+    timeoutMs = DEFAULT_ACQUIRE_TIMEOUT_MS,
+    retryDelayMs = DEFAULT_RETRY_DELAY_MS,
+  }: LockOptions = {}
+): Promise<DistributedLock | null> => {
+  const deadline = Date.now() + timeoutMs;
+  let lock = await acquireLock(resource, ttlMs);
+
+  while (!lock && Date.now() < deadline) {
+    await new Promise((resolve) => setTimeout(resolve, retryDelayMs));
+    lock = await acquireLock(resource, ttlMs);
+  }
+
+  return lock;
+};
+
+/**
+ * Run `fn` while holding the lock for `resource`
+ * Throws LockUnavailableError instead of blocking indefinitely on contention
+ */
+export const withLock = async <T>(
+  resource: string,
+  fn: () => Promise<T>,
+  options?: LockOptions
+): Promise<T> => {
+  const lock = await acquireLockWithTimeout(resource, options);
+  if (!lock) {
+    throw new LockUnavailableError(resource);
+  }
+
+  try {
+    return await fn();
+  } finally {
+    // A failed release must not fail work that already committed, the TTL frees the lock anyway
+    try {
+      await lock.release();
+    } catch (error) {
+      console.warn("Failed to release lock:", lock.key, error);
+    }
+  }
+};
@@ -1,9 +1,59 @@
 import { Ratelimit } from "@upstash/ratelimit";
 import { redis } from ".";
 
+// Requests per hour allowed on the server voice key
+export const VOICE_RATE_LIMIT = 5;
+
 export const voiceRateLimit = new Ratelimit({
   redis,
-  limiter: Ratelimit.slidingWindow(5, "1 h"), // 5 requests per hour
+  limiter: Ratelimit.slidingWindow(VOICE_RATE_LIMIT, "1 h"),
   analytics: true,
   prefix: "voice_transcription",
 });
+
+/**
+ * Attach standard X-RateLimit-* headers (and Retry-After when limited) to a response
+ */
+export const setRateLimitHeaders = (
+  headers: Headers,
+  {
+    success,
+    limit,
+    remaining,
+    reset,
+  }: { success: boolean; limit: number; remaining: number; reset: number }
+): void => {
+  const resetSeconds = Math.ceil(reset / 1000);
+
+  headers.set("X-RateLimit-Limit", String(limit));
+  headers.set("X-RateLimit-Remaining", String(Math.max(0, remaining)));
+  headers.set("X-RateLimit-Reset", String(resetSeconds));
+
+  if (!success) {
+    const retryAfter = Math.max(
+      0,
+      resetSeconds - Math.floor(Date.now() / 1000)
+    );
+    headers.set("Retry-After", String(retryAfter));
+  }
+};
+
+/**
+ * Consume `rate` requests (one by default) from `limiter` and expose the result via response headers
+ * Used by every rate-limited procedure so clients can back off without parsing messages
+ * tRPC procedures passing resHeaders must be sent unbatched, see the splitLink in TRPCProvider
+ */
+export const checkRateLimit = async (
+  limiter: Ratelimit,
+  identifier: string,
+  resHeaders?: Headers,
+  rate = 1
+) => {
+  const result = await limiter.limit(identifier, { rate });
+
+  if (resHeaders) {
+    setRateLimitHeaders(resHeaders, result);
+  }
+
+  return result;
+};
@@ -38,3 +38,11 @@ export const chatRequestSchema = z.object({
 });
 
 export type ChatRequest = z.infer<typeof chatRequestSchema>;
+
+// Share tokens become Redis keys and URL segments, so only URL-safe ids are accepted
+export const partialShareTokenSchema = z
+  .string()
+  .regex(
+    /^[A-Za-z0-9_-]{8,32}$/,
+    "Token must be 8-32 letters, digits, underscores or hyphens"
+  );
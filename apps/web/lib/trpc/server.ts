@@ -3,7 +3,10 @@ import { db } from "@/lib/db";
 import { TRPCError, initTRPC } from "@trpc/server";
 import { ZodError } from "zod";
 
-export const createTRPCContext = async (opts: { headers: Headers }) => {
+export const createTRPCContext = async (opts: {
+  headers: Headers;
+  resHeaders?: Headers;
+}) => {
   const authSession = await auth.api.getSession({
     headers: opts.headers,
   });
@@ -11,6 +14,7 @@ export const createTRPCContext = async (opts: { headers: Headers }) => {
   return {
     db,
     user: authSession?.user,
+    resHeaders: opts.resHeaders,
   };
 };
 export type Context = Awaited<ReturnType<typeof createTRPCContext>>;
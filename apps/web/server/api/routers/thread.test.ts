@@ -0,0 +1,70 @@
+import { branchOutFromMessageAlt } from "@/lib/actions/thread";
+import { threadLockResource, withLock } from "@/lib/redis/lock";
+import { createCallerFactory } from "@/lib/trpc/server";
+import { afterEach, describe, expect, it, vi } from "vitest";
+import { threadRouter } from "./thread";
+
+vi.mock("@/env", () => ({ env: {} }));
+vi.mock("@/lib/auth/server", () => ({ auth: {} }));
+vi.mock("@/lib/db", () => ({ db: {} }));
+vi.mock("@/lib/redis", async () => ({
+  redis: (await import("@/lib/redis/fake-redis")).fakeRedis,
+}));
+vi.mock("@/lib/schema", async () => ({
+  partialShareTokenSchema: (await import("zod")).z.string(),
+}));
+vi.mock("@/lib/actions/partial-share", () => ({}));
+vi.mock("@/lib/actions/usage-limits", () => ({}));
+vi.mock("@/lib/cache/thread-list-cache", () => ({
+  getUserThreadsCacheKey: (userId: string) => `user_threads:${userId}`,
+}));
+vi.mock("@/lib/actions/message-access", async (importOriginal) => ({
+  ...(await importOriginal<typeof import("@/lib/actions/message-access")>()),
+  authorizeMessageAccess: vi.fn(async () => ({
+    message: { threadId: "thread-1" },
+  })),
+}));
+vi.mock("@/lib/actions/thread", () => ({
+  branchOutFromMessageAlt: vi.fn(),
+}));
+
+const createCaller = createCallerFactory(threadRouter);
+const caller = createCaller({
+  db: {} as never,
+  user: { id: "user-1" } as never,
+  resHeaders: new Headers(),
+});
+
+describe("thread.branchOut", () => {
+  afterEach(() => {
+    vi.clearAllMocks();
+  });
+
+  it("lets one of two simultaneous branches through and rejects the other with CONFLICT", async () => {
+    let finishFirst!: () => void;
+    const firstRunning = new Promise<void>((resolve) => {
+      finishFirst = resolve;
+    });
+
+    // Hold the thread lock like the real branch does, until the competing branch has given up
+    vi.mocked(branchOutFromMessageAlt).mockImplementation(
+      ({ originalThreadId, newThreadId }) =>
+        withLock(threadLockResource(originalThreadId), async () => {
+          await firstRunning;
+          return { newThreadId, messageCount: 1 };
+        })
+    );
+
+    const input = { messageId: "message-1", originalThreadId: "thread-1" };
+    const first = caller.branchOut({ ...input, newThreadId: "branch-1" });
+    const second = caller.branchOut({ ...input, newThreadId: "branch-2" });
+
+    await expect(second).rejects.toMatchObject({ code: "CONFLICT" });
+
+    finishFirst();
+    await expect(first).resolves.toEqual({
+      newThreadId: "branch-1",
+      messageCount: 1,
+    });
+  }, 10_000);
+});
@@ -1,8 +1,20 @@
+import {
+  MessageAccessError,
+  authorizeMessageAccess,
+} from "@/lib/actions/message-access";
 import {
   createPartialShare,
   deletePartialShare,
+  forkPartialShare,
+  getPartialShareStats,
+  getThreadPartialShares,
   getUserPartialShares,
+  updatePartialShare,
 } from "@/lib/actions/partial-share";
+import {
+  PartialShareConflictError,
+  PartialShareError,
+} from "@/lib/actions/partial-share-errors";
 import {
   branchOutFromMessageAlt as branchOutFromMessage,
   deleteChat,
@@ -12,12 +24,56 @@ import {
   getUserThreadsCached,
   toggleThreadVisibility,
 } from "@/lib/actions/thread";
+import { getUsageLimits } from "@/lib/actions/usage-limits";
 import { getUserThreadsCacheKey } from "@/lib/cache/thread-list-cache";
+import { OneChatSDKError } from "@/lib/errors";
 import { redis } from "@/lib/redis";
-import { protectedProcedure, router } from "@/lib/trpc/server";
+import { LockUnavailableError } from "@/lib/redis/lock";
+import { partialShareTokenSchema } from "@/lib/schema";
+import {
+  protectedProcedure,
+  publicProcedure,
+  router,
+} from "@/lib/trpc/server";
 import { TRPCError } from "@trpc/server";
 import { z } from "zod";
 
+// A duration such as "7d" or an ISO 8601 timestamp, null for a share that never expires
+const partialShareExpirySchema = z
+  .union([
+    z
+      .string()
+      .regex(/^\d+[mhdw]$/, "Duration must look like 30m, 24h, 7d or 2w"),
+    z.string().datetime({ offset: true }),
+  ])
+  .nullable();
+
+const partialShareModeSchema = z.enum(["snapshot", "live"]);
+
+const toMessageAccessTRPCError = (error: MessageAccessError) =>
+  new TRPCError({
+    code: error.reason === "not_found" ? "NOT_FOUND" : "FORBIDDEN",
+    message: error.message,
+  });
+
+const toPartialShareTRPCError = (error: PartialShareError) =>
+  new TRPCError({
+    code: error.reason === "not_found" ? "NOT_FOUND" : "BAD_REQUEST",
+    message: error.message,
+  });
+
+const isLimitExceededError = (error: unknown): error is OneChatSDKError =>
+  error instanceof OneChatSDKError && error.type === "limit_exceeded";
+
+const toLimitExceededTRPCError = (error: OneChatSDKError) =>
+  new TRPCError({
+    code: "TOO_MANY_REQUESTS",
+    message:
+      typeof error.cause === "string"
+        ? `${error.message} (${error.cause})`
+        : error.message,
+  });
+
 export const threadRouter = router({
   /**
    * Get all threads for the authenticated user
@@ -36,6 +92,21 @@ export const threadRouter = router({
     }
   }),
 
+  /**
+   * Get the user's thread count and the thread/message caps
+   */
+  getUsage: protectedProcedure.query(async ({ ctx }) => {
+    try {
+      return await getUsageLimits(ctx.user.id);
+    } catch (error) {
+      console.error("Error in getUsage:", error);
+      throw new TRPCError({
+        code: "INTERNAL_SERVER_ERROR",
+        message: "Failed to fetch usage",
+      });
+    }
+  }),
+
   /**
    * Delete a thread completely
    * Used for removing threads from sidebar
@@ -59,14 +130,50 @@ export const threadRouter = router({
       }
     }),
 
+  /**
+   * Get a single message by ID
+   * Used for deep links and polling the status of a streaming message
+   */
+  getMessage: publicProcedure
+    .input(z.object({ messageId: z.string() }))
+    .query(async ({ input, ctx }) => {
+      try {
+        const { message } = await authorizeMessageAccess({
+          userId: ctx.user?.id ?? null,
+          messageId: input.messageId,
+          access: "read",
+        });
+        return message;
+      } catch (error) {
+        console.error("Error in getMessage:", error);
+
+        if (error instanceof TRPCError) throw error;
+
+        if (error instanceof MessageAccessError) {
+          throw toMessageAccessTRPCError(error);
+        }
+
+        throw new TRPCError({
+          code: "INTERNAL_SERVER_ERROR",
+          message: "Failed to fetch message",
+        });
+      }
+    }),
+
   /**
    * Delete all messages after a specific message in a thread
    * Used for regenerating conversation from a specific point
    */
   deleteTrailingMessages: protectedProcedure
     .input(z.object({ messageId: z.string() }))
-    .mutation(async ({ input }) => {
+    .mutation(async ({ input, ctx }) => {
       try {
+        await authorizeMessageAccess({
+          userId: ctx.user.id,
+          messageId: input.messageId,
+          access: "write",
+        });
+
         const deletedMessages = await deleteTrailingMessages({
           id: input.messageId,
         });
@@ -80,6 +187,18 @@ export const threadRouter = router({
 
         if (error instanceof TRPCError) throw error;
 
+        if (error instanceof MessageAccessError) {
+          // As before, a message that was never saved has nothing to delete
+          if (error.reason === "not_found") {
+            return { success: true, deletedCount: 0 };
+          }
+          throw toMessageAccessTRPCError(error);
+        }
+
+        if (error instanceof LockUnavailableError) {
+          throw new TRPCError({ code: "CONFLICT", message: error.message });
+        }
+
         throw new TRPCError({
           code: "INTERNAL_SERVER_ERROR",
           message: `Failed to delete trailing messages: ${
@@ -95,8 +214,14 @@ export const threadRouter = router({
    */
   deleteMessageAndTrailing: protectedProcedure
     .input(z.object({ messageId: z.string() }))
-    .mutation(async ({ input }) => {
+    .mutation(async ({ input, ctx }) => {
       try {
+        await authorizeMessageAccess({
+          userId: ctx.user.id,
+          messageId: input.messageId,
+          access: "write",
+        });
+
         const deletedMessages = await deleteMessageAndTrailing({
           id: input.messageId,
         });
@@ -110,6 +235,18 @@ export const threadRouter = router({
 
         if (error instanceof TRPCError) throw error;
 
+        if (error instanceof MessageAccessError) {
+          // As before, a message that was never saved has nothing to delete
+          if (error.reason === "not_found") {
+            return { success: true, deletedCount: 0 };
+          }
+          throw toMessageAccessTRPCError(error);
+        }
+
+        if (error instanceof LockUnavailableError) {
+          throw new TRPCError({ code: "CONFLICT", message: error.message });
+        }
+
         throw new TRPCError({
           code: "INTERNAL_SERVER_ERROR",
           message: `Failed to delete message and trailing messages: ${
@@ -153,6 +290,16 @@ export const threadRouter = router({
     )
     .mutation(async ({ input, ctx }) => {
       try {
+        // Branching only needs read access, so public threads can be branched by anyone signed in
+        const { message } = await authorizeMessageAccess({
+          userId: ctx.user.id,
+          messageId: input.messageId,
+          access: "read",
+        });
+        if (message.threadId !== input.originalThreadId) {
+          throw new MessageAccessError("not_found");
+        }
+
         const resultPromise = branchOutFromMessage({
           messageId: input.messageId,
           userId: ctx.user.id,
@@ -169,6 +316,18 @@ export const threadRouter = router({
 
         if (error instanceof TRPCError) throw error;
 
+        if (error instanceof MessageAccessError) {
+          throw toMessageAccessTRPCError(error);
+        }
+
+        if (isLimitExceededError(error)) {
+          throw toLimitExceededTRPCError(error);
+        }
+
+        if (error instanceof LockUnavailableError) {
+          throw new TRPCError({ code: "CONFLICT", message: error.message });
+        }
+
         throw new TRPCError({
           code: "INTERNAL_SERVER_ERROR",
           message:
@@ -212,8 +371,13 @@ export const threadRouter = router({
     .input(
       z.object({
         threadId: z.string(),
-        messageId: z.string(),
-        token: z.string().optional(),
+        messageId: z.string().optional(), // Optional for live shares
+        token: partialShareTokenSchema.optional(),
+        expiry: partialShareExpirySchema.optional(),
+        mode: partialShareModeSchema.default("snapshot"),
+        reuseExisting: z.boolean().default(false),
+        // Let a reused share move its anchor forward
+        bumpAnchor: z.boolean().default(false),
       })
     )
     .mutation(async ({ input }) => {
@@ -222,6 +386,10 @@ export const threadRouter = router({
           threadId: input.threadId,
           messageId: input.messageId,
           token: input.token,
+          expiry: input.expiry,
+          mode: input.mode,
+          reuseExisting: input.reuseExisting,
+          bumpAnchor: input.bumpAnchor,
         });
         return result;
       } catch (error) {
@@ -229,6 +397,14 @@ export const threadRouter = router({
 
         if (error instanceof TRPCError) throw error;
 
+        if (error instanceof PartialShareError) {
+          throw toPartialShareTRPCError(error);
+        }
+
+        if (error instanceof PartialShareConflictError) {
+          throw new TRPCError({ code: "CONFLICT", message: error.message });
+        }
+
         throw new TRPCError({
           code: "INTERNAL_SERVER_ERROR",
           message:
@@ -262,12 +438,164 @@ export const threadRouter = router({
     }
   }),
 
+  /**
+   * Get the authenticated user's partial shares for a single thread
+   * Used by the share dialog to show existing links
+   */
+  getThreadPartialShares: protectedProcedure
+    .input(z.object({ threadId: z.string() }))
+    .query(async ({ input }) => {
+      try {
+        const partialShares = await getThreadPartialShares(input.threadId);
+        return partialShares;
+      } catch (error) {
+        console.error("Error in getThreadPartialShares:", error);
+
+        if (error instanceof TRPCError) throw error;
+
+        throw new TRPCError({
+          code: "INTERNAL_SERVER_ERROR",
+          message:
+            error instanceof Error
+              ? error.message
+              : "Failed to fetch partial shares",
+        });
+      }
+    }),
+
+  /**
+   * Get view counts for a partial share, bucketed by day
+   * Only available to the owner of the share
+   */
+  getPartialShareStats: protectedProcedure
+    .input(
+      z.object({
+        token: partialShareTokenSchema,
+        days: z.number().int().min(1).max(90).default(30),
+      })
+    )
+    .query(async ({ input }) => {
+      try {
+        const stats = await getPartialShareStats({
+          token: input.token,
+          days: input.days,
+        });
+        return stats;
+      } catch (error) {
+        console.error("Error in getPartialShareStats:", error);
+
+        if (error instanceof TRPCError) throw error;
+
+        if (error instanceof PartialShareError) {
+          throw toPartialShareTRPCError(error);
+        }
+
+        throw new TRPCError({
+          code: "INTERNAL_SERVER_ERROR",
+          message:
+            error instanceof Error
+              ? error.message
+              : "Failed to fetch partial share stats",
+        });
+      }
+    }),
+
+  /**
+   * Update the expiry, mode or shared message of a partial share
+   * Omitted fields are left unchanged, a null expiry makes the share permanent
+   */
+  updatePartialShare: protectedProcedure
+    .input(
+      z.object({
+        token: partialShareTokenSchema,
+        expiry: partialShareExpirySchema.optional(),
+        mode: partialShareModeSchema.optional(),
+        messageId: z.string().nullable().optional(),
+      })
+    )
+    .mutation(async ({ input }) => {
+      try {
+        const result = await updatePartialShare({
+          token: input.token,
+          expiry: input.expiry,
+          mode: input.mode,
+          messageId: input.messageId,
+        });
+        return result;
+      } catch (error) {
+        console.error("Error in updatePartialShare:", error);
+
+        if (error instanceof TRPCError) throw error;
+
+        if (error instanceof PartialShareError) {
+          throw toPartialShareTRPCError(error);
+        }
+
+        throw new TRPCError({
+          code: "INTERNAL_SERVER_ERROR",
+          message:
+            error instanceof Error
+              ? error.message
+              : "Failed to update partial share",
+        });
+      }
+    }),
+
+  /**
+   * Fork a shared conversation into a new private thread owned by the viewer
+   * Copies only the messages visible through the share
+   */
+  forkPartialShare: protectedProcedure
+    .input(
+      z.object({
+        token: partialShareTokenSchema,
+        newThreadId: z.string().optional(),
+      })
+    )
+    .mutation(async ({ input, ctx }) => {
+      try {
+        const resultPromise = forkPartialShare({
+          token: input.token,
+          newThreadId: input.newThreadId,
+        });
+        const threadCachePromise = redis.del(
+          getUserThreadsCacheKey(ctx.user.id)
+        );
+        const [result] = await Promise.all([resultPromise, threadCachePromise]);
+        return result;
+      } catch (error) {
+        console.error("Error in forkPartialShare:", error);
+
+        if (error instanceof TRPCError) throw error;
+
+        if (error instanceof PartialShareError) {
+          throw toPartialShareTRPCError(error);
+        }
+
+        if (isLimitExceededError(error)) {
+          throw toLimitExceededTRPCError(error);
+        }
+
+        if (error instanceof LockUnavailableError) {
+          throw new TRPCError({ code: "CONFLICT", message: error.message });
+        }
+
+        throw new TRPCError({
+          code: "INTERNAL_SERVER_ERROR",
+          message:
+            error instanceof Error
+              ? error.message
+              : "Failed to fork partial share",
+        });
+      }
+    }),
+
   /**
    * Delete a partial share
    * Used for removing partial shares
    */
   deletePartialShare: protectedProcedure
-    .input(z.object({ token: z.string() }))
+    .input(z.object({ token: partialShareTokenSchema }))
     .mutation(async ({ input }) => {
       try {
         const result = await deletePartialShare(input.token);
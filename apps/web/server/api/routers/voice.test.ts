@@ -0,0 +1,134 @@
+import { checkRateLimit } from "@/lib/redis/rate-limits";
+import { createCallerFactory } from "@/lib/trpc/server";
+import { afterEach, beforeEach, describe, expect, it, vi } from "vitest";
+import { voiceRouter } from "./voice";
+
+vi.mock("@/env", () => ({ env: { OPENAI_API_KEY: "sk-server" } }));
+vi.mock("@/lib/auth/server", () => ({ auth: {} }));
+vi.mock("@/lib/db", () => ({ db: {} }));
+vi.mock("@/lib/cache/tts-cache", () => ({
+  getTTSCacheKey: () => "tts:test",
+  getTTSFromCache: vi.fn(async () => null),
+  setTTSCache: vi.fn(),
+}));
+vi.mock("@/lib/redis/rate-limits", () => ({
+  VOICE_RATE_LIMIT: 5,
+  voiceRateLimit: {},
+  checkRateLimit: vi.fn(async () => ({
+    success: true,
+    limit: 5,
+    remaining: 0,
+    reset: Date.now(),
+  })),
+}));
+
+// Sentences of 2100 characters, two never fit in one 4096-character OpenAI chunk
+const textWithChunks = (count: number) =>
+  Array.from({ length: count }, () => `${"a".repeat(2098)}. `).join("");
+
+const createCaller = createCallerFactory(voiceRouter);
+const caller = createCaller({
+  db: {} as never,
+  user: { id: "user-1" } as never,
+  resHeaders: new Headers(),
+});
+
+describe("voice.textToSpeech rate limiting", () => {
+  const fetchMock = vi.fn(async () => new Response(new Uint8Array([1, 2, 3])));
+
+  beforeEach(() => {
+    vi.stubGlobal("fetch", fetchMock);
+  });
+
+  afterEach(() => {
+    vi.unstubAllGlobals();
+    vi.clearAllMocks();
+  });
+
+  it("does not rate limit requests made with the user's own key", async () => {
+    const result = await caller.textToSpeech({
+      text: textWithChunks(7),
+      provider: "openai",
+      apiKey: "sk-user",
+    });
+
+    expect(result.chunks).toBe(7);
+    expect(checkRateLimit).not.toHaveBeenCalled();
+  });
+
+  it("charges the server key one request per chunk", async () => {
+    const result = await caller.textToSpeech({
+      text: textWithChunks(5),
+      provider: "openai",
+    });
+
+    expect(result.chunks).toBe(5);
+    expect(checkRateLimit).toHaveBeenCalledWith(
+      expect.anything(),
+      "voice_user-1",
+      expect.any(Headers),
+      5
+    );
+    expect(fetchMock).toHaveBeenCalledTimes(5);
+  });
+
+  it("rejects text needing more chunks than the hourly limit on the server key", async () => {
+    await expect(
+      caller.textToSpeech({ text: textWithChunks(6), provider: "openai" })
+    ).rejects.toMatchObject({ code: "BAD_REQUEST" });
+
+    expect(checkRateLimit).not.toHaveBeenCalled();
+    expect(fetchMock).not.toHaveBeenCalled();
+  });
+});
+
+describe("voice.textToSpeech input", () => {
+  const fetchMock = vi.fn(async () => new Response(new Uint8Array([1, 2, 3])));
+
+  beforeEach(() => {
+    vi.stubGlobal("fetch", fetchMock);
+  });
+
+  afterEach(() => {
+    vi.unstubAllGlobals();
+    vi.clearAllMocks();
+  });
+
+  it("rejects whitespace-only text", async () => {
+    await expect(
+      caller.textToSpeech({ text: " \n".repeat(5000), provider: "openai" })
+    ).rejects.toMatchObject({ code: "BAD_REQUEST" });
+
+    expect(checkRateLimit).not.toHaveBeenCalled();
+    expect(fetchMock).not.toHaveBeenCalled();
+  });
+
+  it("rejects a model from another provider", async () => {
+    await expect(
+      caller.textToSpeech({
+        text: "Hello there.",
+        provider: "openai",
+        // @ts-expect-error ElevenLabs models are not valid for OpenAI
+        model: "eleven_flash_v2_5",
+      })
+    ).rejects.toMatchObject({ code: "BAD_REQUEST" });
+
+    expect(fetchMock).not.toHaveBeenCalled();
+  });
+
+  it("defaults the model per provider", async () => {
+    await caller.textToSpeech({
+      text: "Hello there.",
+      provider: "elevenlabs",
+      apiKey: "xi-user",
+    });
+
+    const [, init] = fetchMock.mock.calls[0] as unknown as [
+      string,
+      RequestInit,
+    ];
+    expect(JSON.parse(init.body as string)).toMatchObject({
+      model_id: "eleven_multilingual_v2",
+    });
+  });
+});
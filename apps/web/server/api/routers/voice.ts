@@ -1,5 +1,16 @@
 import { env } from "@/env";
-import { voiceRateLimit } from "@/lib/redis/rate-limits";
+import {
+  type TTSCacheEntry,
+  getTTSCacheKey,
+  getTTSFromCache,
+  setTTSCache,
+} from "@/lib/cache/tts-cache";
+import { MAX_TTS_TEXT_LENGTH } from "@/lib/constants";
+import {
+  VOICE_RATE_LIMIT,
+  checkRateLimit,
+  voiceRateLimit,
+} from "@/lib/redis/rate-limits";
 import { protectedProcedure, router } from "@/lib/trpc/server";
 import { TRPCError } from "@trpc/server";
 import { z } from "zod";
@@ -24,6 +35,137 @@ const TTS_CONFIG = {
   format: "mp3",
 } as const;
 
+const ELEVENLABS_CONFIG = {
+  baseUrl: "https://api.elevenlabs.io/v1",
+  model: "eleven_multilingual_v2",
+  voice: "21m00Tcm4TlvDq8ikWAM", // Rachel
+  outputFormat: "mp3_44100_128",
+} as const;
+
+const DEFAULT_VOICES = {
+  openai: "alloy",
+  google: "Kore",
+  elevenlabs: ELEVENLABS_CONFIG.voice,
+} as const;
+
+// Per-request input limits, longer text is split into chunks and the audio concatenated
+const TTS_CHUNK_LIMITS = {
+  openai: 4096,
+  google: 4000,
+  elevenlabs: 5000,
+} as const;
+
+const TTS_MODELS = {
+  openai: ["gpt-4o-mini-tts", "tts-1", "tts-1-hd"],
+  google: ["gemini-2.5-flash-preview-tts", "gemini-2.5-pro-preview-tts"],
+  elevenlabs: [
+    "eleven_multilingual_v2",
+    "eleven_turbo_v2_5",
+    "eleven_flash_v2_5",
+  ],
+} as const;
+
+const DEFAULT_TTS_MODELS = {
+  openai: TTS_CONFIG.model,
+  google: "gemini-2.5-flash-preview-tts",
+  elevenlabs: ELEVENLABS_CONFIG.model,
+} as const;
+
+type TTSProvider = keyof typeof TTS_CHUNK_LIMITS;
+
+type TTSModel = (typeof TTS_MODELS)[TTSProvider][number];
+
+// Each provider only accepts its own models, a model sent to the wrong provider is a bad request
+const ttsModelSchema = z.discriminatedUnion("provider", [
+  z.object({
+    provider: z.literal("openai"),
+    model: z.enum(TTS_MODELS.openai).default(DEFAULT_TTS_MODELS.openai),
+  }),
+  z.object({
+    provider: z.literal("google"),
+    model: z.enum(TTS_MODELS.google).default(DEFAULT_TTS_MODELS.google),
+  }),
+  z.object({
+    provider: z.literal("elevenlabs"),
+    model: z
+      .enum(TTS_MODELS.elevenlabs)
+      .default(DEFAULT_TTS_MODELS.elevenlabs),
+  }),
+]);
+
+interface SpeechRequest {
+  text: string;
+  model: TTSModel;
+  voice: string;
+  speed: number;
+  stability?: number;
+  similarity?: number;
+  apiKey: string;
+}
+
+const PROVIDER_NAMES = {
+  openai: "OpenAI",
+  google: "Google AI",
+  elevenlabs: "ElevenLabs",
+} as const;
+
+// Server-side keys used when the user has not provided their own
+const SERVER_API_KEYS: Record<TTSProvider, string | undefined> = {
+  openai: env.OPENAI_API_KEY,
+  google: undefined,
+  elevenlabs: undefined,
+};
+
+/**
+ * Rate limit voice requests served with a server API key
+ * Throws TOO_MANY_REQUESTS with the wait time once the hourly limit is exhausted
+ */
+const enforceVoiceRateLimit = async (
+  userId: string,
+  resHeaders?: Headers,
+  rate = 1
+) => {
+  const { success, limit, reset } = await checkRateLimit(
+    voiceRateLimit,
+    `voice_${userId}`,
+    resHeaders,
+    rate
+  );
+
+  if (!success) {
+    const waitMinutes = Math.ceil((reset - Date.now()) / 60000);
+    throw new TRPCError({
+      code: "TOO_MANY_REQUESTS",
+      message: `Voice limit reached (${limit}/hour). Try again in ${waitMinutes}m or add your API key.`,
+    });
+  }
+};
+
+/**
+ * Map ElevenLabs error responses to TRPC errors
+ * ElevenLabs reports exhausted character quota as a 401 with a quota_exceeded status
+ */
+function getElevenLabsError(status: number, errorText: string): TRPCError {
+  if (status === 429 || errorText.includes("quota_exceeded")) {
+    return new TRPCError({
+      code: "TOO_MANY_REQUESTS",
+      message: "ElevenLabs quota exceeded. Check your plan or try again later.",
+    });
+  }
+
+  if (status === 401) {
+    return new TRPCError({
+      code: "UNAUTHORIZED",
+      message: "Invalid ElevenLabs API key",
+    });
+  }
+
+  return new TRPCError({
+    code: "INTERNAL_SERVER_ERROR",
+    message: `Failed to generate speech: ${status}`,
+  });
+}
+
 /**
  * Create a WAV file from PCM16 data
  */
@@ -74,6 +216,189 @@ function createWavFile(
   return Buffer.concat([header, pcmData]);
 }
 
+/**
+ * Split text into chunks of at most `maxLength` characters
+ * Prefers sentence boundaries, then word boundaries, and only hard-splits as a last resort
+ */
+function splitTextForSpeech(text: string, maxLength: number): string[] {
+  if (text.length <= maxLength) return [text];
+
+  const sentenceSegmenter = new Intl.Segmenter(undefined, {
+    granularity: "sentence",
+  });
+  const pieces: string[] = [];
+
+  for (const { segment } of sentenceSegmenter.segment(text)) {
+    if (segment.length <= maxLength) {
+      pieces.push(segment);
+      continue;
+    }
+
+    // Oversized sentence: fall back to words, then to fixed-size slices
+    for (const word of segment.split(/(?<=\s)/)) {
+      for (let offset = 0; offset < word.length; offset += maxLength) {
+        pieces.push(word.slice(offset, offset + maxLength));
+      }
+    }
+  }
+
+  const chunks: string[] = [];
+  let current = "";
+
+  for (const piece of pieces) {
+    if (current.length + piece.length > maxLength) {
+      chunks.push(current);
+      current = "";
+    }
+    current += piece;
+  }
+
+  if (current) chunks.push(current);
+
+  return chunks.map((chunk) => chunk.trim()).filter(Boolean);
+}
+
+/**
+ * OpenAI TTS API, returns mp3 audio
+ */
+async function synthesizeOpenAISpeech({
+  text,
+  model,
+  voice,
+  speed,
+  apiKey,
+}: SpeechRequest): Promise<Buffer> {
+  const response = await fetch("https://api.openai.com/v1/audio/speech", {
+    method: "POST",
+    headers: {
+      Authorization: `Bearer ${apiKey}`,
+      "Content-Type": "application/json",
+    },
+    body: JSON.stringify({
+      model,
+      input: text,
+      voice,
+      response_format: TTS_CONFIG.format,
+      speed,
+    }),
+  });
+
+  if (!response.ok) {
+    const errorText = await response.text();
+    console.error("OpenAI TTS API error:", errorText);
+    throw new TRPCError({
+      code: "INTERNAL_SERVER_ERROR",
+      message: `Failed to generate speech: ${response.status}`,
+    });
+  }
+
+  return Buffer.from(await response.arrayBuffer());
+}
+
+/**
+ * Google Gemini TTS API, returns raw PCM16 (24kHz, mono) without a WAV header
+ */
+async function synthesizeGeminiSpeech({
+  text,
+  model,
+  voice,
+  apiKey,
+}: SpeechRequest): Promise<Buffer> {
+  const response = await fetch(
+    `https://generativelanguage.googleapis.com/v1beta/models/${model}:generateContent?key=${apiKey}`,
+    {
+      method: "POST",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify({
+        contents: [{ parts: [{ text }] }],
+        generationConfig: {
+          responseModalities: ["AUDIO"],
+          speechConfig: {
+            voiceConfig: {
+              prebuiltVoiceConfig: {
+                voiceName: voice,
+              },
+            },
+          },
+        },
+      }),
+    }
+  );
+
+  if (!response.ok) {
+    const errorText = await response.text();
+    console.error("Gemini TTS API error:", errorText);
+    throw new TRPCError({
+      code: "INTERNAL_SERVER_ERROR",
+      message: `Failed to generate speech: ${response.status}`,
+    });
+  }
+
+  const data = await response.json();
+  const audioData = data.candidates?.[0]?.content?.parts?.[0]?.inlineData?.data;
+
+  if (!audioData) {
+    throw new TRPCError({
+      code: "INTERNAL_SERVER_ERROR",
+      message: "Invalid response from Gemini API",
+    });
+  }
+
+  return Buffer.from(audioData, "base64");
+}
+
+/**
+ * ElevenLabs TTS API, voice is the ElevenLabs voice ID, returns mp3 audio
+ */
+async function synthesizeElevenLabsSpeech({
+  text,
+  model,
+  voice,
+  stability,
+  similarity,
+  apiKey,
+}: SpeechRequest): Promise<Buffer> {
+  const response = await fetch(
+    `${ELEVENLABS_CONFIG.baseUrl}/text-to-speech/${encodeURIComponent(
+      voice
+    )}?output_format=${ELEVENLABS_CONFIG.outputFormat}`,
+    {
+      method: "POST",
+      headers: {
+        "xi-api-key": apiKey,
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify({
+        text,
+        model_id: model,
+        voice_settings: {
+          stability: stability ?? 0.5,
+          similarity_boost: similarity ?? 0.75,
+        },
+      }),
+    }
+  );
+
+  if (!response.ok) {
+    const errorText = await response.text();
+    console.error("ElevenLabs TTS API error:", errorText);
+    throw getElevenLabsError(response.status, errorText);
+  }
+
+  return Buffer.from(await response.arrayBuffer());
+}
+
+const SPEECH_SYNTHESIZERS: Record<
+  TTSProvider,
+  (request: SpeechRequest) => Promise<Buffer>
+> = {
+  openai: synthesizeOpenAISpeech,
+  google: synthesizeGeminiSpeech,
+  elevenlabs: synthesizeElevenLabsSpeech,
+};
+
 export const voiceRouter = router({
   /**
    * Generate a temporary client token for OpenAI Realtime API
@@ -101,17 +426,7 @@ export const voiceRouter = router({
 
       // Rate limit free users (no API key provided)
       if (!hasUserApiKey) {
-        const rateLimitKey = `voice_${user.id}`;
-        const { success, limit, reset } =
-          await voiceRateLimit.limit(rateLimitKey);
-
-        if (!success) {
-          const waitMinutes = Math.ceil((reset - Date.now()) / 60000);
-          throw new TRPCError({
-            code: "TOO_MANY_REQUESTS",
-            message: `Voice limit reached (${limit}/hour). Try again in ${waitMinutes}m or add your API key.`,
-          });
-        }
+        await enforceVoiceRateLimit(user.id, ctx.resHeaders);
       }
 
       // Create transcription session
@@ -165,141 +480,131 @@ export const voiceRouter = router({
     }),
 
   /**
-   * Convert text to speech using OpenAI or Gemini TTS API
+   * Convert text to speech using OpenAI, Gemini or ElevenLabs TTS API
    * Returns audio data as base64 encoded string
    */
   textToSpeech: protectedProcedure
     .input(
-      z.object({
-        text: z.string().min(1).max(4096), // OpenAI TTS has 4096 character limit
-        voice: z.string().default("alloy"), // Support both OpenAI and Gemini voices
-        model: z
-          .enum([
-            "gpt-4o-mini-tts",
-            "tts-1",
-            "tts-1-hd",
-            "gemini-2.5-flash-preview-tts",
-            "gemini-2.5-pro-preview-tts",
-          ])
-          .default("gpt-4o-mini-tts"),
-        speed: z.number().min(0.25).max(4.0).default(1.0),
-        apiKey: z.string().optional(),
-        provider: z.enum(["openai", "google"]).default("openai"),
-      })
+      z
+        .object({
+          // Trimmed so whitespace-only text is rejected instead of splitting into no chunks
+          text: z
+            .string()
+            .trim()
+            .min(1)
+            .max(
+              MAX_TTS_TEXT_LENGTH,
+              `Text is too long, maximum is ${MAX_TTS_TEXT_LENGTH} characters`
+            ),
+          voice: z.string().optional(), // OpenAI/Gemini voice name or ElevenLabs voice ID
+          speed: z.number().min(0.25).max(4.0).default(1.0),
+          stability: z.number().min(0).max(1).optional(), // ElevenLabs only
+          similarity: z.number().min(0).max(1).optional(), // ElevenLabs only
+          apiKey: z.string().optional(),
+          cache: z.boolean().default(true),
+        })
+        .and(ttsModelSchema)
     )
-    .mutation(async ({ input }) => {
-      const { text, voice, model, speed, apiKey, provider } = input;
-      const hasUserApiKey = Boolean(apiKey);
+    .mutation(async ({ input, ctx }) => {
+      const {
+        text,
+        model,
+        speed,
+        stability,
+        similarity,
+        apiKey,
+        provider,
+        cache,
+      } = input;
+      const voice = input.voice ?? DEFAULT_VOICES[provider];
+
+      const effectiveApiKey = apiKey || SERVER_API_KEYS[provider];
 
       // Validate API key availability
-      if (!hasUserApiKey) {
+      if (!effectiveApiKey) {
         throw new TRPCError({
           code: "INTERNAL_SERVER_ERROR",
-          message: `${
-            provider === "openai" ? "OpenAI" : "Google AI"
-          } API key not configured`,
+          message: `${PROVIDER_NAMES[provider]} API key not configured`,
         });
       }
 
-      try {
-        if (provider === "openai") {
-          // OpenAI TTS API
-          const response = await fetch(
-            "https://api.openai.com/v1/audio/speech",
-            {
-              method: "POST",
-              headers: {
-                Authorization: `Bearer ${apiKey}`,
-                "Content-Type": "application/json",
-              },
-              body: JSON.stringify({
-                model,
-                input: text,
-                voice,
-                response_format: TTS_CONFIG.format,
-                speed,
-              }),
-            }
-          );
+      // Replaying the same message aloud is common, so serve repeats from Redis
+      const cacheKey = getTTSCacheKey({
+        provider,
+        model,
+        voice,
+        speed,
+        stability,
+        similarity,
+        text,
+      });
+
+      if (cache) {
+        const cached = await getTTSFromCache(cacheKey);
+        if (cached) {
+          ctx.resHeaders?.set("x-cache", "hit");
+          return cached;
+        }
+      }
 
-          if (!response.ok) {
-            const errorText = await response.text();
-            console.error("OpenAI TTS API error:", errorText);
-            throw new TRPCError({
-              code: "INTERNAL_SERVER_ERROR",
-              message: `Failed to generate speech: ${response.status}`,
-            });
-          }
+      ctx.resHeaders?.set("x-cache", "miss");
 
-          // Convert audio response to base64
-          const audioBuffer = await response.arrayBuffer();
-          const audioBase64 = Buffer.from(audioBuffer).toString("base64");
+      // Providers cap input length, so long text is synthesized chunk by chunk
+      const chunks = splitTextForSpeech(text, TTS_CHUNK_LIMITS[provider]);
 
-          return {
-            audio: audioBase64,
-            format: TTS_CONFIG.format,
-            voice,
-            text_length: text.length,
-          };
+      // Rate limit free users (no API key provided), charging one request per provider call
+      // Cache hits above are not counted
+      if (!apiKey) {
+        // More chunks than the hourly allowance could never pass, so waiting would not help
+        if (chunks.length > VOICE_RATE_LIMIT) {
+          throw new TRPCError({
+            code: "BAD_REQUEST",
+            message:
+              "Text is too long for the shared voice key. Add your API key or shorten it.",
+          });
         }
 
-        if (provider === "google") {
-          // Google Gemini TTS API
-          const response = await fetch(
-            `https://generativelanguage.googleapis.com/v1beta/models/${model}:generateContent?key=${apiKey}`,
-            {
-              method: "POST",
-              headers: {
-                "Content-Type": "application/json",
-              },
-              body: JSON.stringify({
-                contents: [{ parts: [{ text }] }],
-                generationConfig: {
-                  responseModalities: ["AUDIO"],
-                  speechConfig: {
-                    voiceConfig: {
-                      prebuiltVoiceConfig: {
-                        voiceName: voice,
-                      },
-                    },
-                  },
-                },
-              }),
-            }
-          );
-
-          if (!response.ok) {
-            const errorText = await response.text();
-            console.error("Gemini TTS API error:", errorText);
-            throw new TRPCError({
-              code: "INTERNAL_SERVER_ERROR",
-              message: `Failed to generate speech: ${response.status}`,
-            });
-          }
+        await enforceVoiceRateLimit(ctx.user.id, ctx.resHeaders, chunks.length);
+      }
 
-          const data = await response.json();
-          const audioData =
-            data.candidates?.[0]?.content?.parts?.[0]?.inlineData?.data;
+      try {
+        const synthesize = SPEECH_SYNTHESIZERS[provider];
+        const audioChunks: Buffer[] = [];
+
+        for (const chunk of chunks) {
+          audioChunks.push(
+            await synthesize({
+              text: chunk,
+              model,
+              voice,
+              speed,
+              stability,
+              similarity,
+              apiKey: effectiveApiKey,
+            })
+          );
+        }
 
-          if (!audioData) {
-            throw new TRPCError({
-              code: "INTERNAL_SERVER_ERROR",
-              message: "Invalid response from Gemini API",
-            });
-          }
+        const isGemini = provider === "google";
+        const joined = Buffer.concat(audioChunks);
+
+        const result: TTSCacheEntry = {
+          // Gemini returns raw PCM16 data, so wrap the joined samples in a single WAV header (24kHz, mono)
+          // mp3 frames can be concatenated as-is
+          audio: (isGemini ? createWavFile(joined, 24000, 1) : joined).toString(
+            "base64"
+          ),
+          format: isGemini ? "wav" : TTS_CONFIG.format,
+          voice,
+          text_length: text.length,
+          chunks: chunks.length,
+        };
 
-          // Gemini returns raw PCM16 data, we need to create a proper WAV file
-          const pcmBuffer = Buffer.from(audioData, "base64");
-          const wavBuffer = createWavFile(pcmBuffer, 24000, 1); // 24kHz, mono
-          const wavBase64 = wavBuffer.toString("base64");
-
-          return {
-            audio: wavBase64,
-            format: "wav",
-            voice,
-            text_length: text.length,
-          };
+        if (cache) {
+          setTTSCache(cacheKey, result);
         }
+
+        return result;
       } catch (error) {
         if (error instanceof TRPCError) throw error;
 
@@ -309,4 +614,45 @@ export const voiceRouter = router({
         });
       }
     }),
+
+  /**
+   * List the voices available to an ElevenLabs account
+   * A mutation rather than a query so the API key is sent in the body, not the URL
+   */
+  listVoices: protectedProcedure
+    .input(
+      z.object({
+        provider: z.enum(["elevenlabs"]).default("elevenlabs"),
+        apiKey: z.string().min(1),
+      })
+    )
+    .mutation(async ({ input }) => {
+      const response = await fetch(`${ELEVENLABS_CONFIG.baseUrl}/voices`, {
+        headers: {
+          "xi-api-key": input.apiKey,
+        },
+      });
+
+      if (!response.ok) {
+        const errorText = await response.text();
+        console.error("ElevenLabs voices API error:", errorText);
+        throw getElevenLabsError(response.status, errorText);
+      }
+
+      const data = (await response.json()) as {
+        voices?: {
+          voice_id: string;
+          name: string;
+          category?: string;
+          preview_url?: string;
+        }[];
+      };
+
+      return (data.voices ?? []).map((voice) => ({
+        id: voice.voice_id,
+        name: voice.name,
+        category: voice.category ?? null,
+        preview_url: voice.preview_url ?? null,
+      }));
+    }),
 });